@@ -0,0 +1,241 @@
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{bail, Result};
+use futures::{future, stream, FutureExt, StreamExt, TryStreamExt};
+
+use crate::checkpoint::{Checkpoint, GifStatus};
+use crate::client::{GiphyClient, Verbosity};
+use crate::download::report_error;
+use crate::error::GiphyError;
+use crate::known::KnownGifs;
+use crate::source::Source;
+
+/// Options for [`GiphyClient::archive`]
+#[derive(Clone, Debug, Default)]
+pub struct ArchiveOptions {
+    /// Continue from the checkpoint saved by a previous run, skipping GIFs it
+    /// already downloaded
+    pub resume: bool,
+    /// Stop paginating after this many consecutive GIFs that are already in
+    /// the archive, only for channel feeds
+    pub incremental: Option<usize>,
+}
+
+/// Outcome of archiving a member's channel
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ArchiveSummary {
+    /// GIFs downloaded or found already archived
+    pub archived: usize,
+    /// GIFs that failed to download
+    pub failed: usize,
+}
+
+/// Download progress shared between the feed and the download results
+struct Progress {
+    checkpoint: Checkpoint,
+    /// Pages in feed order that are not fully processed yet
+    pages: VecDeque<PageProgress>,
+    /// Index of the front of `pages`
+    first_page: usize,
+    /// Pagination stopped before the end of the feed
+    stopped: bool,
+}
+
+struct PageProgress {
+    remaining: usize,
+    next: Option<String>,
+}
+
+impl Progress {
+    /// Move the cursor past fully processed pages, returns whether it moved
+    fn advance(&mut self) -> bool {
+        let mut advanced = false;
+        while self.pages.front().is_some_and(|p| p.remaining == 0) {
+            let page = self.pages.pop_front().unwrap();
+            self.first_page += 1;
+            self.checkpoint.cursor = page.next;
+            advanced = true;
+        }
+        advanced
+    }
+}
+
+impl GiphyClient {
+    /// Archive several sources into `dir` concurrently
+    ///
+    /// Downloads of all sources share the client's concurrency limit. Returns
+    /// the outcome of each source in the given order.
+    pub async fn archive_sources(
+        &self,
+        sources: &[Source],
+        dir: impl AsRef<Path>,
+        options: &ArchiveOptions,
+    ) -> Vec<(Source, Result<ArchiveSummary>)> {
+        let dir = dir.as_ref();
+        let archives = sources.iter().map(|source| {
+            self.archive_source(source, dir, options)
+                .map(move |r| (source.clone(), r))
+        });
+        future::join_all(archives).await
+    }
+
+    /// Archive a member's channel into `dir`, checkpointing progress to a state file
+    pub async fn archive(
+        &self,
+        member_id: u64,
+        dir: impl AsRef<Path>,
+        options: &ArchiveOptions,
+    ) -> Result<ArchiveSummary> {
+        self.archive_source(&Source::Channel(member_id), dir, options)
+            .await
+    }
+
+    /// Archive the GIFs of a source into `dir`, checkpointing progress to a
+    /// state file
+    pub async fn archive_source(
+        &self,
+        source: &Source,
+        dir: impl AsRef<Path>,
+        options: &ArchiveOptions,
+    ) -> Result<ArchiveSummary> {
+        let dir = dir.as_ref();
+        if options.incremental.is_some() && !source.is_channel() {
+            bail!(GiphyError::IncrementalSource {
+                name: source.to_string(),
+            });
+        }
+        let key = source.key();
+        let checkpoint = match options.resume {
+            true => Checkpoint::load(dir, &key).await?,
+            false => None,
+        }
+        .unwrap_or_else(|| Checkpoint::new(&key));
+        let start = match &checkpoint.cursor {
+            Some(cursor) => self.next_url(cursor)?,
+            None => self.source_url(source)?,
+        };
+        let follow = match options.incremental {
+            Some(threshold) => {
+                let mut known = KnownGifs::scan(dir).await?;
+                if let (Some(catalog), Source::Channel(member_id)) = (&self.catalog, source) {
+                    for (id, path) in catalog.member_files(*member_id)? {
+                        if Path::new(&path).exists() {
+                            known.insert(&id);
+                        }
+                    }
+                }
+                for (id, _) in checkpoint
+                    .gifs
+                    .iter()
+                    .filter(|(_, s)| **s == GifStatus::Done)
+                {
+                    known.insert(id);
+                }
+                Some((known, threshold))
+            }
+            None => None,
+        };
+        let progress = Mutex::new(Progress {
+            checkpoint,
+            pages: VecDeque::new(),
+            first_page: 0,
+            stopped: false,
+        });
+
+        // Feed is newest first, so a run of known GIFs means the rest is archived
+        let mut streak = 0;
+        let pages = self.paginate(Ok(start), |page| {
+            let (known, threshold) = match &follow {
+                Some(f) => f,
+                None => return true,
+            };
+            for gif in &page.results {
+                streak = if known.contains(gif) { streak + 1 } else { 0 };
+                if streak >= *threshold {
+                    if self.prints(Verbosity::Normal) {
+                        println!("Reached {} archived GIFs, stopping", streak);
+                    }
+                    progress.lock().unwrap().stopped = true;
+                    return false;
+                }
+            }
+            true
+        });
+
+        // Tag GIFs with their page so the cursor only moves past finished pages
+        let gifs = pages
+            .map_ok(|page| {
+                let mut progress = progress.lock().unwrap();
+                let index = progress.first_page + progress.pages.len();
+                let gifs: Vec<_> = page
+                    .results
+                    .into_iter()
+                    .filter(|gif| !progress.checkpoint.is_done(&gif.id))
+                    .collect();
+                let next = match progress.stopped {
+                    true => None,
+                    false => page.next,
+                };
+                progress.pages.push_back(PageProgress {
+                    remaining: gifs.len(),
+                    next,
+                });
+                stream::iter(gifs.into_iter().map(move |gif| Ok((index, gif))))
+            })
+            .try_flatten();
+
+        let results = gifs
+            .map_ok(|(page, gif)| {
+                let id = gif.id.clone();
+                self.download_gif(gif, dir).map(move |r| Ok((page, id, r)))
+            })
+            .try_buffer_unordered(self.concurrency);
+        futures::pin_mut!(results);
+
+        let mut summary = ArchiveSummary::default();
+        let mut feed_error = None;
+        while let Some(r) = results.next().await {
+            let checkpoint = {
+                let mut progress = progress.lock().unwrap();
+                match r {
+                    Ok((page, id, result)) => {
+                        let status = match result {
+                            Ok(()) => {
+                                summary.archived += 1;
+                                GifStatus::Done
+                            }
+                            Err(e) => {
+                                report_error(&e);
+                                summary.failed += 1;
+                                GifStatus::Failed
+                            }
+                        };
+                        progress.checkpoint.gifs.insert(id, status);
+                        let index = page - progress.first_page;
+                        progress.pages[index].remaining -= 1;
+                    }
+                    Err(e) => feed_error = Some(e),
+                }
+                progress.advance().then(|| progress.checkpoint.clone())
+            };
+            if let Some(checkpoint) = checkpoint {
+                checkpoint.save(dir).await?;
+            }
+        }
+
+        // Pages without pending GIFs may remain after the last download
+        let checkpoint = {
+            let mut progress = progress.lock().unwrap();
+            progress.advance();
+            progress.checkpoint.clone()
+        };
+        checkpoint.save(dir).await?;
+
+        match feed_error {
+            Some(e) => Err(e),
+            None => Ok(summary),
+        }
+    }
+}
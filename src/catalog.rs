@@ -0,0 +1,326 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+
+use crate::types::GiphyGif;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS members (
+    id INTEGER PRIMARY KEY,
+    username TEXT NOT NULL,
+    name TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS gifs (
+    id TEXT PRIMARY KEY,
+    index_id INTEGER,
+    member_id INTEGER,
+    title TEXT NOT NULL,
+    create_time TEXT NOT NULL,
+    first_seen TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS gifs_member ON gifs (member_id, create_time);
+CREATE TABLE IF NOT EXISTS renditions (
+    gif_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    url TEXT,
+    width INTEGER,
+    height INTEGER,
+    size INTEGER,
+    PRIMARY KEY (gif_id, name)
+);
+CREATE TABLE IF NOT EXISTS files (
+    gif_id TEXT NOT NULL,
+    rendition TEXT NOT NULL,
+    path TEXT NOT NULL,
+    size INTEGER,
+    sha256 TEXT,
+    archived_at TEXT NOT NULL,
+    PRIMARY KEY (gif_id, rendition)
+);
+CREATE TABLE IF NOT EXISTS attempts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    gif_id TEXT NOT NULL,
+    rendition TEXT,
+    url TEXT,
+    attempted_at TEXT NOT NULL,
+    error TEXT
+);
+CREATE TABLE IF NOT EXISTS objects (
+    sha256 TEXT PRIMARY KEY,
+    path TEXT NOT NULL,
+    size INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS files_sha256 ON files (sha256);
+CREATE VIEW IF NOT EXISTS failures AS
+    SELECT * FROM attempts WHERE error IS NOT NULL;
+";
+
+/// A GIF recorded in the catalog
+#[derive(Clone, Debug)]
+pub struct CatalogGif {
+    pub id: String,
+    /// `None` for GIFs only seen outside of channel feeds
+    pub index_id: Option<u64>,
+    /// `None` for anonymous uploads
+    pub member_id: Option<u64>,
+    pub title: String,
+    pub create_time: String,
+}
+
+/// A file recorded in the catalog
+#[derive(Clone, Debug)]
+pub struct CatalogFile {
+    pub gif_id: String,
+    pub rendition: String,
+    pub path: String,
+    pub size: Option<u64>,
+    pub sha256: Option<String>,
+    pub archived_at: DateTime<Utc>,
+}
+
+/// A failed download attempt
+#[derive(Clone, Debug)]
+pub struct CatalogFailure {
+    pub gif_id: String,
+    /// `None` if the GIF failed before a rendition was chosen
+    pub rendition: Option<String>,
+    pub url: Option<String>,
+    pub attempted_at: DateTime<Utc>,
+    pub error: String,
+}
+
+/// SQLite database of the members, GIFs, files and download attempts of an
+/// archive
+///
+/// Feed pages are recorded as they are fetched and downloads as they finish.
+/// The tables can also be queried directly through [`Catalog::connection`].
+#[derive(Debug)]
+pub struct Catalog {
+    conn: Mutex<Connection>,
+}
+
+impl Catalog {
+    /// Open or create a catalog database
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    /// Create a catalog that only lives in memory
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Underlying connection for ad hoc queries
+    pub fn connection(&self) -> std::sync::MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap()
+    }
+
+    /// Add or update GIFs along with their members and renditions
+    pub fn record_gifs<'a>(&self, gifs: impl IntoIterator<Item = &'a GiphyGif>) -> Result<()> {
+        let mut conn = self.connection();
+        let tx = conn.transaction()?;
+        let now = Utc::now();
+        for gif in gifs {
+            if let Some(user) = &gif.user {
+                tx.execute(
+                    "INSERT INTO members (id, username, name) VALUES (?1, ?2, ?3)
+                     ON CONFLICT (id) DO UPDATE SET username = ?2, name = ?3",
+                    params![user.id, user.username, user.name],
+                )?;
+            }
+            tx.execute(
+                "INSERT INTO gifs (id, index_id, member_id, title, create_time, first_seen, data)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                 ON CONFLICT (id) DO UPDATE SET
+                    index_id = ?2, member_id = ?3, title = ?4, create_time = ?5, data = ?7",
+                params![
+                    gif.id,
+                    gif.index_id,
+                    gif.user.as_ref().map(|u| u.id),
+                    gif.title,
+                    gif.create_time,
+                    now,
+                    serde_json::to_string(&gif.raw)?,
+                ],
+            )?;
+            for (name, rendition) in gif.images.iter() {
+                tx.execute(
+                    "INSERT OR REPLACE INTO renditions (gif_id, name, url, width, height, size)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        gif.id,
+                        name,
+                        rendition.url(None),
+                        rendition.width,
+                        rendition.height,
+                        rendition.size(None),
+                    ],
+                )?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Record an archived file and the attempt that downloaded it
+    pub fn record_file(&self, file: &CatalogFile, url: Option<&str>) -> Result<()> {
+        let mut conn = self.connection();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO files (gif_id, rendition, path, size, sha256, archived_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                file.gif_id,
+                file.rendition,
+                file.path,
+                file.size,
+                file.sha256,
+                file.archived_at,
+            ],
+        )?;
+        if let Some(url) = url {
+            tx.execute(
+                "INSERT INTO attempts (gif_id, rendition, url, attempted_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![file.gif_id, file.rendition, url, Utc::now()],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Record a file in the content store, the archived files referring to
+    /// it are those with the same SHA-256
+    pub fn record_object(&self, sha256: &str, path: &Path, size: u64) -> Result<()> {
+        self.connection().execute(
+            "INSERT OR REPLACE INTO objects (sha256, path, size) VALUES (?1, ?2, ?3)",
+            params![sha256, path.to_string_lossy(), size],
+        )?;
+        Ok(())
+    }
+
+    /// Record a failed download attempt
+    pub fn record_failure(
+        &self,
+        gif_id: &str,
+        rendition: Option<&str>,
+        url: Option<&str>,
+        error: &anyhow::Error,
+    ) -> Result<()> {
+        self.connection().execute(
+            "INSERT INTO attempts (gif_id, rendition, url, attempted_at, error)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![gif_id, rendition, url, Utc::now(), format!("{:#}", error)],
+        )?;
+        Ok(())
+    }
+
+    /// GIFs of a member in feed order, optionally only those created in `year`
+    pub fn member_gifs(&self, member_id: u64, year: Option<i32>) -> Result<Vec<CatalogGif>> {
+        let conn = self.connection();
+        let mut stmt = conn.prepare(
+            "SELECT id, index_id, member_id, title, create_time FROM gifs
+             WHERE member_id = ?1 AND (?2 IS NULL OR substr(create_time, 1, 4) = ?2)
+             ORDER BY index_id DESC",
+        )?;
+        let gifs = stmt
+            .query_map(
+                params![member_id, year.map(|y| format!("{:04}", y))],
+                |row| {
+                    Ok(CatalogGif {
+                        id: row.get(0)?,
+                        index_id: row.get(1)?,
+                        member_id: row.get(2)?,
+                        title: row.get(3)?,
+                        create_time: row.get(4)?,
+                    })
+                },
+            )?
+            .collect::<Result<_, _>>()?;
+        Ok(gifs)
+    }
+
+    /// Archived files of a GIF
+    pub fn files(&self, gif_id: &str) -> Result<Vec<CatalogFile>> {
+        let conn = self.connection();
+        let mut stmt = conn.prepare(
+            "SELECT gif_id, rendition, path, size, sha256, archived_at FROM files
+             WHERE gif_id = ?1 ORDER BY rendition",
+        )?;
+        let files = stmt
+            .query_map(params![gif_id], file_from_row)?
+            .collect::<Result<_, _>>()?;
+        Ok(files)
+    }
+
+    /// Archived files with the SHA-256 `sha256`, i.e. the references to a
+    /// file in the content store
+    pub fn references(&self, sha256: &str) -> Result<Vec<CatalogFile>> {
+        let conn = self.connection();
+        let mut stmt = conn.prepare(
+            "SELECT gif_id, rendition, path, size, sha256, archived_at FROM files
+             WHERE sha256 = ?1 ORDER BY path",
+        )?;
+        let files = stmt
+            .query_map(params![sha256], file_from_row)?
+            .collect::<Result<_, _>>()?;
+        Ok(files)
+    }
+
+    /// Paths of the archived files of a member
+    pub fn member_files(&self, member_id: u64) -> Result<Vec<(String, String)>> {
+        let conn = self.connection();
+        let mut stmt = conn.prepare(
+            "SELECT files.gif_id, files.path FROM files
+             JOIN gifs ON gifs.id = files.gif_id WHERE gifs.member_id = ?1",
+        )?;
+        let files = stmt
+            .query_map(params![member_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        Ok(files)
+    }
+
+    /// Failed download attempts since `since`, most recent first
+    pub fn failures_since(&self, since: DateTime<Utc>) -> Result<Vec<CatalogFailure>> {
+        let conn = self.connection();
+        let mut stmt = conn.prepare(
+            "SELECT gif_id, rendition, url, attempted_at, error FROM failures
+             WHERE attempted_at >= ?1 ORDER BY attempted_at DESC, id DESC",
+        )?;
+        let failures = stmt
+            .query_map(params![since], |row| {
+                Ok(CatalogFailure {
+                    gif_id: row.get(0)?,
+                    rendition: row.get(1)?,
+                    url: row.get(2)?,
+                    attempted_at: row.get(3)?,
+                    error: row.get(4)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(failures)
+    }
+}
+
+/// File from a `gif_id, rendition, path, size, sha256, archived_at` row
+fn file_from_row(row: &rusqlite::Row) -> rusqlite::Result<CatalogFile> {
+    Ok(CatalogFile {
+        gif_id: row.get(0)?,
+        rendition: row.get(1)?,
+        path: row.get(2)?,
+        size: row.get(3)?,
+        sha256: row.get(4)?,
+        archived_at: row.get(5)?,
+    })
+}
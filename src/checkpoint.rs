@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use tokio::fs;
+
+use crate::sanitize::MAX_COMPONENT_BYTES;
+
+/// Crawl progress of a feed, persisted in the download directory
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Checkpoint {
+    /// [`Source::key`](crate::Source::key) of the feed, the member ID for
+    /// channels
+    #[serde(alias = "member_id", deserialize_with = "string_or_number")]
+    pub source: String,
+    /// `next` link of the first page that has not been fully processed,
+    /// `None` to start from the first page
+    pub cursor: Option<String>,
+    /// Completion status of processed GIFs by id
+    pub gifs: BTreeMap<String, GifStatus>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum GifStatus {
+    Done,
+    Failed,
+}
+
+impl Checkpoint {
+    const PREFIX: &'static str = ".giphy-download-";
+    /// Longest source key whose state file name, including the temporary
+    /// `.json.tmp` one, stays within [`MAX_COMPONENT_BYTES`]
+    pub(crate) const MAX_SOURCE_BYTES: usize =
+        MAX_COMPONENT_BYTES - Self::PREFIX.len() - ".json.tmp".len();
+
+    pub fn new(source: impl fmt::Display) -> Self {
+        Self {
+            source: source.to_string(),
+            cursor: None,
+            gifs: BTreeMap::new(),
+        }
+    }
+
+    /// Location of the state file for a source key
+    pub fn path(dir: impl AsRef<Path>, source: impl fmt::Display) -> PathBuf {
+        dir.as_ref()
+            .join(format!("{}{}.json", Self::PREFIX, source))
+    }
+
+    /// Load a source's state file, `None` if there is none
+    pub async fn load(dir: impl AsRef<Path>, source: impl fmt::Display) -> Result<Option<Self>> {
+        let path = Self::path(dir, source);
+        let text = match fs::read_to_string(&path).await {
+            Ok(t) => t,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let checkpoint = serde_json::from_str(&text)
+            .with_context(|| format!("Invalid state file {}", path.to_string_lossy()))?;
+        Ok(Some(checkpoint))
+    }
+
+    /// Write the state file, replacing any previous one atomically
+    pub async fn save(&self, dir: impl AsRef<Path>) -> Result<()> {
+        let path = Self::path(&dir, &self.source);
+        let tmp = path.with_extension("json.tmp");
+        fs::create_dir_all(dir).await?;
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?).await?;
+        fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    pub fn is_done(&self, id: &str) -> bool {
+        self.gifs.get(id) == Some(&GifStatus::Done)
+    }
+}
+
+/// State files of older versions stored the numeric member ID
+fn string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(s) => Ok(s),
+        serde_json::Value::Number(n) => Ok(n.to_string()),
+        other => Err(de::Error::custom(format!(
+            "expected a string or number, found {}",
+            other
+        ))),
+    }
+}
@@ -0,0 +1,418 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use futures::{stream, Stream};
+use reqwest::Url;
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+
+use crate::catalog::Catalog;
+use crate::download::FileLocks;
+use crate::error::GiphyError;
+use crate::limit::{HostLimits, TokenBucket};
+use crate::manifest::Manifests;
+use crate::rendition::RenditionSelection;
+use crate::retry::RetryPolicy;
+use crate::source::Source;
+use crate::store::ContentStore;
+use crate::template::Template;
+use crate::types::{GiphyGif, GiphyResponse};
+
+/// Default Giphy API base URL
+pub const DEFAULT_BASE_URL: &str = "https://giphy.com";
+
+/// Default number of GIFs downloaded at the same time
+pub const DEFAULT_CONCURRENCY: usize = 20;
+
+/// Amount of progress a client prints, failed downloads are always reported
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
+pub enum Verbosity {
+    /// Nothing but failures
+    Quiet,
+    /// Fetched pages, downloads and retries
+    #[default]
+    Normal,
+    /// Also files that are skipped because they're already archived
+    Verbose,
+}
+
+/// Giphy API client
+#[derive(Clone, Debug)]
+pub struct GiphyClient {
+    pub(crate) client: reqwest::Client,
+    base_url: Url,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) renditions: RenditionSelection,
+    pub(crate) sidecars: bool,
+    pub(crate) store: Option<ContentStore>,
+    pub(crate) filename_template: Template,
+    pub(crate) dir_template: Template,
+    pub(crate) concurrency: usize,
+    /// Download permits shared by all clones of the client
+    pub(crate) download_slots: Arc<Semaphore>,
+    pub(crate) host_limits: HostLimits,
+    /// Limit of API requests per second
+    pub(crate) request_rate: Option<TokenBucket>,
+    /// Limit of media bytes per second across all downloads
+    pub(crate) bandwidth: Option<TokenBucket>,
+    pub(crate) prefetch: bool,
+    pub(crate) verbosity: Verbosity,
+    pub(crate) manifests: Manifests,
+    pub(crate) file_locks: FileLocks,
+    pub(crate) catalog: Option<Arc<Catalog>>,
+}
+
+impl GiphyClient {
+    /// Create a client with the default settings
+    pub fn new() -> Result<Self> {
+        Self::builder().build()
+    }
+
+    /// Create a client from an existing `reqwest::Client`
+    pub fn from_client(client: reqwest::Client) -> Self {
+        Self {
+            client,
+            base_url: Url::parse(DEFAULT_BASE_URL).unwrap(),
+            retry: RetryPolicy::default(),
+            renditions: RenditionSelection::default(),
+            sidecars: false,
+            store: None,
+            filename_template: Template::default_filename(),
+            dir_template: Template::default_dir(),
+            concurrency: DEFAULT_CONCURRENCY,
+            download_slots: Arc::new(Semaphore::new(DEFAULT_CONCURRENCY)),
+            host_limits: HostLimits::default(),
+            request_rate: None,
+            bandwidth: None,
+            prefetch: false,
+            verbosity: Verbosity::default(),
+            manifests: Manifests::default(),
+            file_locks: FileLocks::default(),
+            catalog: None,
+        }
+    }
+
+    pub fn builder() -> GiphyClientBuilder {
+        GiphyClientBuilder::default()
+    }
+
+    /// API base URL used for all feed requests
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// Whether progress of `level` is printed
+    pub(crate) fn prints(&self, level: Verbosity) -> bool {
+        self.verbosity >= level
+    }
+
+    /// Catalog recording fetched GIFs and downloads, if any
+    pub fn catalog(&self) -> Option<&Catalog> {
+        self.catalog.as_deref()
+    }
+
+    /// Stream every GIF in a member's channel feed
+    ///
+    /// Pages are fetched lazily as the stream is polled.
+    pub fn gifs(&self, member_id: u64) -> impl Stream<Item = Result<GiphyGif>> + '_ {
+        self.source_gifs(&Source::Channel(member_id))
+    }
+
+    /// Stream the pages of a member's channel feed
+    pub fn pages(&self, member_id: u64) -> impl Stream<Item = Result<GiphyResponse>> + '_ {
+        self.source_pages(&Source::Channel(member_id))
+    }
+
+    /// Stream feed pages starting at `url`, following `next` links
+    pub fn pages_from(&self, url: Url) -> impl Stream<Item = Result<GiphyResponse>> + '_ {
+        self.paginate(Ok(url), |_| true)
+    }
+
+    /// URL of the first page of a member's channel feed
+    pub fn feed_url(&self, member_id: u64) -> Result<Url> {
+        self.api_url(&format!("api/v4/channels/{}/feed", member_id))
+    }
+
+    /// Stream feed pages starting at `first`, following `next` links while
+    /// `follow` returns true for the page just fetched
+    ///
+    /// With prefetching enabled the next page is requested in the background
+    /// as soon as a page is returned.
+    pub(crate) fn paginate<'a>(
+        &'a self,
+        first: Result<Url>,
+        follow: impl FnMut(&GiphyResponse) -> bool + 'a,
+    ) -> impl Stream<Item = Result<GiphyResponse>> + 'a {
+        stream::try_unfold(
+            (Some(first), None, 1, follow),
+            move |(url, prefetched, i, mut follow)| async move {
+                let url = match url {
+                    Some(u) => u?,
+                    None => return Ok(None),
+                };
+
+                let page = match prefetched {
+                    Some(handle) => handle.await??,
+                    None => {
+                        if self.prints(Verbosity::Normal) {
+                            println!("Fetching page {}", i);
+                        }
+                        self.page(url).await?
+                    }
+                };
+                if let Some(catalog) = &self.catalog {
+                    catalog.record_gifs(&page.results)?;
+                }
+
+                // Check for more
+                let next = match follow(&page) {
+                    true => page.next.as_deref().map(|u| self.next_url(u)),
+                    false => None,
+                };
+                let prefetched = match (&next, self.prefetch) {
+                    (Some(Ok(url)), true) => {
+                        if self.prints(Verbosity::Normal) {
+                            println!("Prefetching page {}", i + 1);
+                        }
+                        Some(self.spawn_page(url.clone()))
+                    }
+                    _ => None,
+                };
+                Ok(Some((page, (next, prefetched, i + 1, follow))))
+            },
+        )
+    }
+
+    /// Fetch a feed page in the background
+    fn spawn_page(&self, url: Url) -> JoinHandle<Result<GiphyResponse>> {
+        let client = self.clone();
+        tokio::spawn(async move { client.page(url).await })
+    }
+
+    /// Fetch a single feed page
+    async fn page(&self, url: Url) -> Result<GiphyResponse> {
+        let text = self.get_text(&url).await?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Resolve an API path relative to the base URL
+    pub(crate) fn api_url(&self, path: &str) -> Result<Url> {
+        Ok(self.base_url.join(path.trim_start_matches('/'))?)
+    }
+
+    /// Resolve a `next` pagination link against the base URL
+    ///
+    /// Giphy returns absolute links to its own host, so links pointing at a
+    /// different origin than the base URL keep only their path and query.
+    pub fn next_url(&self, next: &str) -> Result<Url> {
+        let next = self.base_url.join(next)?;
+        if next.origin() == self.base_url.origin() {
+            return Ok(next);
+        }
+
+        let mut path = next.path().to_owned();
+        if let Some(query) = next.query() {
+            path.push('?');
+            path.push_str(query);
+        }
+        self.api_url(&path)
+    }
+}
+
+/// Builder for [`GiphyClient`]
+#[derive(Debug)]
+pub struct GiphyClientBuilder {
+    client: Option<reqwest::Client>,
+    base_url: String,
+    timeout: Duration,
+    retry: RetryPolicy,
+    renditions: RenditionSelection,
+    sidecars: bool,
+    store: Option<ContentStore>,
+    filename_template: Template,
+    dir_template: Template,
+    concurrency: usize,
+    connections_per_host: Option<usize>,
+    requests_per_second: Option<f64>,
+    bytes_per_second: Option<u64>,
+    prefetch: bool,
+    verbosity: Verbosity,
+    catalog: Option<Catalog>,
+}
+
+impl Default for GiphyClientBuilder {
+    fn default() -> Self {
+        Self {
+            client: None,
+            base_url: DEFAULT_BASE_URL.to_owned(),
+            timeout: Duration::from_secs(30),
+            retry: RetryPolicy::default(),
+            renditions: RenditionSelection::default(),
+            sidecars: false,
+            store: None,
+            filename_template: Template::default_filename(),
+            dir_template: Template::default_dir(),
+            concurrency: DEFAULT_CONCURRENCY,
+            connections_per_host: None,
+            requests_per_second: None,
+            bytes_per_second: None,
+            prefetch: false,
+            verbosity: Verbosity::default(),
+            catalog: None,
+        }
+    }
+}
+
+impl GiphyClientBuilder {
+    /// Use an existing `reqwest::Client`, ignoring the timeout setting
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// API base URL, e.g. a staging proxy or a local mock server
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Request timeout
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Retry policy for feed and media requests
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Renditions to download for each GIF
+    pub fn renditions(mut self, renditions: RenditionSelection) -> Self {
+        self.renditions = renditions;
+        self
+    }
+
+    /// Write a JSON sidecar with the feed entry and download facts next to
+    /// every downloaded file
+    pub fn sidecars(mut self, sidecars: bool) -> Self {
+        self.sidecars = sidecars;
+        self
+    }
+
+    /// Save downloads once in a content-addressed store and link to them
+    /// from the member directories
+    pub fn content_store(mut self, store: ContentStore) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// File name of each downloaded rendition, relative to its directory
+    pub fn filename_template(mut self, template: Template) -> Self {
+        self.filename_template = template;
+        self
+    }
+
+    /// Directory of each downloaded rendition, relative to the download
+    /// directory
+    pub fn dir_template(mut self, template: Template) -> Self {
+        self.dir_template = template;
+        self
+    }
+
+    /// Maximum number of GIFs downloaded at the same time, shared by all
+    /// archives run with the client
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Maximum number of concurrent connections to each host, unlimited by
+    /// default
+    pub fn connections_per_host(mut self, connections: usize) -> Self {
+        self.connections_per_host = Some(connections);
+        self
+    }
+
+    /// Maximum rate of feed and other API requests, media downloads are not
+    /// counted
+    pub fn requests_per_second(mut self, rate: f64) -> Self {
+        self.requests_per_second = Some(rate);
+        self
+    }
+
+    /// Maximum bandwidth shared by all media downloads
+    pub fn bytes_per_second(mut self, rate: u64) -> Self {
+        self.bytes_per_second = Some(rate);
+        self
+    }
+
+    /// Fetch the next feed page while the GIFs of the current one are
+    /// processed
+    pub fn prefetch_pages(mut self, prefetch: bool) -> Self {
+        self.prefetch = prefetch;
+        self
+    }
+
+    /// Amount of progress printed
+    pub fn verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    /// Record fetched GIFs and downloads in a catalog database
+    pub fn catalog(mut self, catalog: Catalog) -> Self {
+        self.catalog = Some(catalog);
+        self
+    }
+
+    pub fn build(self) -> Result<GiphyClient> {
+        let mut base_url = Url::parse(&self.base_url).map_err(|_| GiphyError::InvalidBaseUrl {
+            url: self.base_url.clone(),
+        })?;
+        if base_url.cannot_be_a_base() {
+            bail!(GiphyError::InvalidBaseUrl { url: self.base_url });
+        }
+
+        // Treat the base URL as a directory so joined paths are appended to it
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+
+        let client = match self.client {
+            Some(c) => c,
+            None => reqwest::Client::builder().timeout(self.timeout).build()?,
+        };
+
+        Ok(GiphyClient {
+            client,
+            base_url,
+            retry: self.retry,
+            renditions: self.renditions,
+            sidecars: self.sidecars,
+            store: self.store,
+            filename_template: self.filename_template,
+            dir_template: self.dir_template,
+            concurrency: self.concurrency,
+            download_slots: Arc::new(Semaphore::new(self.concurrency)),
+            host_limits: HostLimits::new(self.connections_per_host),
+            // Capacity of one request, so requests are spaced evenly
+            request_rate: self
+                .requests_per_second
+                .filter(|r| *r > 0.0)
+                .map(|r| TokenBucket::new(r, 1.0)),
+            // Allow a second worth of bytes at once, then keep to the rate
+            bandwidth: self
+                .bytes_per_second
+                .filter(|r| *r > 0)
+                .map(|r| TokenBucket::new(r as f64, r as f64)),
+            prefetch: self.prefetch,
+            verbosity: self.verbosity,
+            manifests: Manifests::default(),
+            file_locks: FileLocks::default(),
+            catalog: self.catalog.map(Arc::new),
+        })
+    }
+}
@@ -0,0 +1,55 @@
+use std::path::Path;
+
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use tokio::fs;
+use tokio::io::AsyncReadExt;
+
+/// Size and SHA-256 of a file
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FileDigest {
+    pub size: u64,
+    /// Lowercase hex SHA-256
+    pub sha256: String,
+}
+
+/// Incrementally computed [`FileDigest`]
+#[derive(Default)]
+pub(crate) struct Digester {
+    hasher: Sha256,
+    size: u64,
+}
+
+impl Digester {
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+        self.size += data.len() as u64;
+    }
+
+    /// Add the contents of a file
+    pub(crate) async fn update_file(&mut self, path: &Path) -> Result<()> {
+        let mut file = fs::File::open(path).await?;
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let n = file.read(&mut buf).await?;
+            if n == 0 {
+                return Ok(());
+            }
+            self.update(&buf[..n]);
+        }
+    }
+
+    pub(crate) fn finish(self) -> FileDigest {
+        FileDigest {
+            size: self.size,
+            sha256: format!("{:x}", self.hasher.finalize()),
+        }
+    }
+}
+
+/// Compute the digest of a file
+pub async fn digest_file(path: impl AsRef<Path>) -> Result<FileDigest> {
+    let mut digester = Digester::default();
+    digester.update_file(path.as_ref()).await?;
+    Ok(digester.finish())
+}
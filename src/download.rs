@@ -0,0 +1,533 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use futures::{Future, FutureExt, Stream, StreamExt, TryStreamExt};
+use reqwest::header::{CONTENT_RANGE, IF_RANGE, RANGE};
+use reqwest::{Response, StatusCode, Url};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::catalog::CatalogFile;
+use crate::client::{GiphyClient, Verbosity};
+use crate::digest::{digest_file, Digester, FileDigest};
+use crate::error::GiphyError;
+use crate::manifest::ManifestEntry;
+use crate::partial::{part_path, remove_partial, PartInfo};
+use crate::rendition::{RenditionLayout, RenditionSpec};
+use crate::sanitize::sanitize_path;
+use crate::sidecar::{DownloadInfo, Sidecar};
+use crate::types::GiphyGif;
+
+/// Print a failed download and its causes
+pub(crate) fn report_error(e: &anyhow::Error) {
+    eprintln!("Failed to download {}", e);
+    e.chain().skip(1).for_each(|cause| eprintln!("  {}", cause));
+}
+
+/// Locks of the files being downloaded by a client and its clones
+pub(crate) type FileLocks = Arc<std::sync::Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>>;
+
+/// Minimum time between progress reports of a download
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+impl GiphyClient {
+    /// Download GIFs into `dir` as they arrive, reporting failures without aborting
+    ///
+    /// If the GIF stream itself fails, pending downloads are finished before
+    /// the error is returned.
+    pub async fn download(
+        &self,
+        gifs: impl Stream<Item = Result<GiphyGif>>,
+        dir: impl AsRef<Path>,
+    ) -> Result<()> {
+        let dir = dir.as_ref();
+        let results = gifs
+            .map_ok(|gif| self.download_gif(gif, dir).map(Ok))
+            .try_buffer_unordered(self.concurrency);
+        futures::pin_mut!(results);
+        let mut feed_error = None;
+        while let Some(r) = results.next().await {
+            match r {
+                Ok(Ok(())) => (),
+                Ok(Err(e)) => report_error(&e),
+                Err(e) => feed_error = Some(e),
+            }
+        }
+
+        match feed_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Download a single GIF into `base_dir`
+    pub async fn download_gif(&self, gif: GiphyGif, base_dir: impl AsRef<Path>) -> Result<()> {
+        let _permit = self.download_slots.acquire().await?;
+        let id = gif.id.clone();
+        self._download_gif(gif, base_dir)
+            .await
+            .context(format!("id: {}", id))
+    }
+
+    async fn _download_gif(&self, gif: GiphyGif, base_dir: impl AsRef<Path>) -> Result<()> {
+        let files = match self.plan_files(&gif) {
+            Ok(files) => files,
+            Err(e) => {
+                if let Some(catalog) = &self.catalog {
+                    catalog.record_failure(&gif.id, None, None, &e)?;
+                }
+                return Err(e);
+            }
+        };
+
+        // Every rendition is attempted even if an earlier one failed
+        let total = files.len();
+        let mut errors = Vec::new();
+        for file in files {
+            let member_dir = base_dir.as_ref().join(&file.dir);
+            if let Err(e) = self
+                .download_file(&gif, file.spec, &file.url, &member_dir, &file.filename)
+                .await
+            {
+                errors.push(e);
+            }
+        }
+
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            failed => bail!(
+                "{} of {} renditions failed: {}",
+                failed,
+                total,
+                errors
+                    .iter()
+                    .map(|e| format!("{:#}", e))
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            ),
+        }
+    }
+
+    /// Selected renditions of `gif` with their URLs and names from the
+    /// filename and directory templates
+    fn plan_files<'a>(&'a self, gif: &'a GiphyGif) -> Result<Vec<PlannedFile<'a>>> {
+        // Get rendition urls
+        let selected = self.renditions.select(gif);
+        if selected.is_empty() {
+            bail!(GiphyError::MissingRendition {
+                wanted: self
+                    .renditions
+                    .renditions
+                    .iter()
+                    .map(|r| r.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            });
+        }
+
+        // Generate file names
+        selected
+            .into_iter()
+            .map(|(spec, url)| {
+                let url = Url::parse(url).map_err(|_| GiphyError::InvalidSourceVideo)?;
+                let ext = extension(&url).ok_or(GiphyError::InvalidSourceVideo)?;
+                let dir = sanitize_path(&self.dir_template.render(gif, spec, ext)?);
+                let name = self.filename_template.render(gif, spec, ext)?;
+                let filename = match self.renditions.all {
+                    None => name,
+                    Some(RenditionLayout::Subfolder) => format!("{}/{}", spec.label(), name),
+                    Some(RenditionLayout::Suffix) => {
+                        match name.strip_suffix(&format!(".{}", ext)) {
+                            Some(stem) => format!("{}.{}.{}", stem, spec.label(), ext),
+                            None => format!("{}.{}", name, spec.label()),
+                        }
+                    }
+                };
+                Ok(PlannedFile {
+                    spec,
+                    url,
+                    dir,
+                    filename: sanitize_path(&filename),
+                })
+            })
+            .collect()
+    }
+
+    /// Download a rendition of `gif` from `url` to `filename` in `member_dir`
+    /// unless the manifest shows it's already archived, failures are recorded
+    /// in the catalog
+    async fn download_file(
+        &self,
+        gif: &GiphyGif,
+        spec: &RenditionSpec,
+        url: &Url,
+        member_dir: &Path,
+        filename: &str,
+    ) -> Result<()> {
+        // Only one download of a path at a time, later ones find it archived
+        let path = member_dir.join(filename);
+        let result = self
+            .with_file_lock(
+                path,
+                self._download_file(gif, spec, url, member_dir, filename),
+            )
+            .await;
+
+        if let (Some(catalog), Err(e)) = (&self.catalog, &result) {
+            catalog.record_failure(&gif.id, Some(&spec.to_string()), Some(url.as_str()), e)?;
+        }
+        result
+    }
+
+    /// Run `f` while holding the lock of `path`
+    async fn with_file_lock<T>(&self, path: PathBuf, f: impl Future<Output = T>) -> T {
+        let lock = self
+            .file_locks
+            .lock()
+            .unwrap()
+            .entry(path.clone())
+            .or_default()
+            .clone();
+        let guard = lock.lock().await;
+        let result = f.await;
+        drop(guard);
+        let mut locks = self.file_locks.lock().unwrap();
+        if Arc::strong_count(&lock) == 2 {
+            locks.remove(&path);
+        }
+        result
+    }
+
+    async fn _download_file(
+        &self,
+        gif: &GiphyGif,
+        spec: &RenditionSpec,
+        url: &Url,
+        member_dir: &Path,
+        filename: &str,
+    ) -> Result<()> {
+        let path = member_dir.join(filename);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        let manifest = self.manifest(member_dir).await?;
+        let recorded = manifest.lock().await.get(filename).cloned();
+
+        // Check if a complete file exists, downloads are only moved into
+        // place once complete but the file may have been damaged since.
+        // A damaged file is kept until its replacement has been downloaded.
+        let part = part_path(&path);
+        let existing = match fs::metadata(&path).await {
+            Ok(metadata) => Some(metadata.len()),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        let expected = recorded.as_ref().and_then(|e| e.size);
+        let complete = matches!(
+            existing,
+            Some(len) if len > 0 && expected.is_none_or(|expected| expected == len)
+        );
+        if let (Some(len), false) = (existing, complete) {
+            if self.prints(Verbosity::Normal) {
+                println!(
+                    "Replacing incomplete {} ({} of {} bytes)",
+                    path.to_string_lossy(),
+                    len,
+                    expected.unwrap_or_default()
+                );
+            }
+        }
+
+        let (size, sha256, archived_at, downloaded) = if complete {
+            remove_partial(&path).await?;
+            let backfill_sidecar = self.sidecars && !Sidecar::path(&path).exists();
+            let metadata = fs::metadata(&path).await?;
+            if let Some(entry) = &recorded {
+                // The file may have changed since it was archived, so a
+                // missing sidecar is filled in from the manifest entry
+                if let (true, Some(sha256)) = (backfill_sidecar, &entry.sha256) {
+                    let download = DownloadInfo {
+                        rendition: spec.to_string(),
+                        source_url: url.to_string(),
+                        size: entry.size.unwrap_or(metadata.len()),
+                        sha256: sha256.clone(),
+                        downloaded_at: entry.archived_at,
+                    };
+                    save_sidecar(gif, download, &path).await?;
+                }
+                if self.prints(Verbosity::Verbose) {
+                    println!("Already archived {}", path.to_string_lossy());
+                }
+                self.catalog_file(&path, entry, None)?;
+                return Ok(());
+            }
+
+            // Fill in files archived without a manifest entry
+            let sha256 = match backfill_sidecar {
+                true => Some(digest_file(&path).await?.sha256),
+                false => None,
+            };
+            (metadata.len(), sha256, metadata.modified()?.into(), false)
+        } else {
+            let digest = self
+                .with_retry(url, || self.download_to(url, &part))
+                .await?;
+            // Content-Length was checked while downloading, the size Giphy
+            // lists in the feed is not always accurate
+            let listed = spec.size(gif).filter(|&size| size != digest.size);
+            if let (Some(listed), true) = (listed, self.prints(Verbosity::Normal)) {
+                eprintln!(
+                    "Warning: {} has {} bytes but Giphy lists {}",
+                    url, digest.size, listed
+                );
+            }
+            match &self.store {
+                Some(store) => {
+                    // Identical media downloaded concurrently is stored once
+                    let object = self
+                        .with_file_lock(store.object_path(&digest.sha256), async {
+                            let object = store.insert(&part, &digest).await?;
+                            store.link(&object, &path).await?;
+                            anyhow::Ok(object)
+                        })
+                        .await?;
+                    if let Some(catalog) = &self.catalog {
+                        catalog.record_object(&digest.sha256, &object, digest.size)?;
+                    }
+                }
+                None => fs::rename(&part, &path).await?,
+            }
+            if self.prints(Verbosity::Normal) {
+                println!(
+                    "Downloaded {} ({} bytes)",
+                    path.to_string_lossy(),
+                    digest.size
+                );
+            }
+            (digest.size, Some(digest.sha256), Utc::now(), true)
+        };
+        if let (true, Some(sha256)) = (self.sidecars, &sha256) {
+            let download = DownloadInfo {
+                rendition: spec.to_string(),
+                source_url: url.to_string(),
+                size,
+                sha256: sha256.clone(),
+                downloaded_at: archived_at,
+            };
+            save_sidecar(gif, download, &path).await?;
+        }
+
+        let entry = ManifestEntry {
+            filename: filename.to_owned(),
+            id: gif.id.clone(),
+            index_id: gif.index_id,
+            title: gif.title.clone(),
+            create_time: gif.create_time.clone(),
+            rendition: spec.to_string(),
+            size: Some(size),
+            sha256,
+            archived_at,
+        };
+        self.catalog_file(&path, &entry, downloaded.then_some(url))?;
+        manifest.lock().await.insert(entry).await?;
+
+        Ok(())
+    }
+
+    /// Record an archived file in the catalog, along with the download
+    /// attempt if it was fetched from `url`
+    fn catalog_file(&self, path: &Path, entry: &ManifestEntry, url: Option<&Url>) -> Result<()> {
+        let catalog = match &self.catalog {
+            Some(c) => c,
+            None => return Ok(()),
+        };
+        let file = CatalogFile {
+            gif_id: entry.id.clone(),
+            rendition: entry.rendition.clone(),
+            path: path.to_string_lossy().into_owned(),
+            size: entry.size,
+            sha256: entry.sha256.clone(),
+            archived_at: entry.archived_at,
+        };
+        catalog.record_file(&file, url.map(Url::as_str))
+    }
+
+    /// Stream the body of `url` into `path`, returns the digest of the file
+    ///
+    /// An existing partial file is continued with a range request if the
+    /// resource hasn't changed since it was started.
+    async fn download_to(&self, url: &Url, path: &Path) -> Result<FileDigest> {
+        let _permit = self.host_limits.acquire(url).await;
+        let (mut resp, offset) = self.resume_request(url, path).await?;
+        let mut digester = Digester::default();
+        let mut file = match offset {
+            0 => {
+                match PartInfo::from_response(url, &resp) {
+                    Some(info) => info.save(path).await?,
+                    None => PartInfo::remove(path).await?,
+                }
+                fs::File::create(path).await?
+            }
+            _ => {
+                if self.prints(Verbosity::Normal) {
+                    println!("Resuming {} at {} bytes", path.to_string_lossy(), offset);
+                }
+                digester.update_file(path).await?;
+                fs::OpenOptions::new().append(true).open(path).await?
+            }
+        };
+
+        let total = resp.content_length().map(|len| len + offset);
+        let mut progress = ByteProgress::new(path, total, self.prints(Verbosity::Normal));
+        progress.written = offset;
+        while let Some(chunk) = resp.chunk().await? {
+            if let Some(bandwidth) = &self.bandwidth {
+                bandwidth.take(chunk.len() as f64).await;
+            }
+            file.write_all(&chunk).await?;
+            digester.update(&chunk);
+            progress.update(chunk.len() as u64);
+        }
+        file.sync_all().await?;
+
+        // A connection closed early leaves the partial file to resume from
+        if let Some(expected) = total.filter(|&total| total != progress.written) {
+            bail!(GiphyError::IncompleteDownload {
+                url: url.to_string(),
+                expected,
+                actual: progress.written,
+            });
+        }
+        PartInfo::remove(path).await?;
+        Ok(digester.finish())
+    }
+
+    /// Request `url`, continuing the partial download at `path` if possible
+    ///
+    /// Returns the response and the offset its body starts at.
+    async fn resume_request(&self, url: &Url, path: &Path) -> Result<(Response, u64)> {
+        let offset = fs::metadata(path).await.map(|m| m.len()).unwrap_or(0);
+        let info = match offset {
+            0 => None,
+            _ => PartInfo::load(path, url).await,
+        };
+
+        if let Some(validator) = info.as_ref().and_then(|i| i.validator()) {
+            let request = self
+                .client
+                .get(url.clone())
+                .header(RANGE, format!("bytes={}-", offset))
+                .header(IF_RANGE, validator);
+            match self.send(request, url).await {
+                Ok(resp) if resp.status() == StatusCode::PARTIAL_CONTENT => {
+                    if content_range_start(&resp) == Some(offset) {
+                        return Ok((resp, offset));
+                    }
+                }
+                // Resource changed, the full body was returned
+                Ok(resp) => return Ok((resp, 0)),
+                Err(e) if !is_status(&e, StatusCode::RANGE_NOT_SATISFIABLE) => return Err(e),
+                Err(_) => (),
+            }
+        }
+
+        Ok((self.get(url).await?, 0))
+    }
+}
+
+/// A rendition of a GIF to download
+struct PlannedFile<'a> {
+    spec: &'a RenditionSpec,
+    url: Url,
+    /// Sanitized directory relative to the download directory
+    dir: String,
+    /// Sanitized path relative to `dir`
+    filename: String,
+}
+
+/// File extension of the last path segment of `url`
+/// Write the sidecar of the downloaded file at `path`
+async fn save_sidecar(gif: &GiphyGif, download: DownloadInfo, path: &Path) -> Result<()> {
+    let sidecar = Sidecar {
+        gif: gif.raw.clone(),
+        download,
+    };
+    sidecar.save(path).await
+}
+
+fn extension(url: &Url) -> Option<&str> {
+    let (_, ext) = url.path_segments()?.next_back()?.rsplit_once('.')?;
+    (!ext.is_empty()).then_some(ext)
+}
+
+/// First byte position of a `Content-Range` header
+fn content_range_start(resp: &Response) -> Option<u64> {
+    resp.headers()
+        .get(CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes ")?
+        .split_once('-')?
+        .0
+        .parse()
+        .ok()
+}
+
+fn is_status(e: &anyhow::Error, status: StatusCode) -> bool {
+    matches!(
+        e.downcast_ref(),
+        Some(GiphyError::ResponseError { code, .. }) if *code == status.as_u16()
+    )
+}
+
+/// Periodically printed byte progress of a single download
+struct ByteProgress<'a> {
+    path: &'a Path,
+    total: Option<u64>,
+    written: u64,
+    last_report: Instant,
+    /// Whether reports are printed
+    enabled: bool,
+}
+
+impl<'a> ByteProgress<'a> {
+    fn new(path: &'a Path, total: Option<u64>, enabled: bool) -> Self {
+        Self {
+            path,
+            total,
+            written: 0,
+            last_report: Instant::now(),
+            enabled,
+        }
+    }
+
+    fn update(&mut self, len: u64) {
+        self.written += len;
+        if !self.enabled || self.last_report.elapsed() < PROGRESS_INTERVAL {
+            return;
+        }
+        self.last_report = Instant::now();
+
+        match self.total {
+            Some(total) if total > 0 => println!(
+                "Downloading {}: {} of {} bytes ({:.0}%)",
+                self.path.to_string_lossy(),
+                self.written,
+                total,
+                self.written as f64 * 100.0 / total as f64
+            ),
+            _ => println!(
+                "Downloading {}: {} bytes",
+                self.path.to_string_lossy(),
+                self.written
+            ),
+        }
+    }
+}
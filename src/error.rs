@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum GiphyError {
+    #[error("Received response error status {code} for url {url}")]
+    ResponseError {
+        code: u16,
+        url: String,
+        /// Delay requested by the server before retrying
+        retry_after: Option<Duration>,
+    },
+    #[error("Invalid source video found")]
+    InvalidSourceVideo,
+    #[error("None of the renditions {wanted} found")]
+    MissingRendition { wanted: String },
+    #[error("Invalid rendition {name}")]
+    InvalidRendition { name: String },
+    #[error("Invalid rendition layout {name}, expected subfolder or suffix")]
+    InvalidLayout { name: String },
+    #[error("Invalid date {date}")]
+    InvalidTime { date: String },
+    #[error("Invalid base url {url}")]
+    InvalidBaseUrl { url: String },
+    #[error("Invalid member {member}, expected an ID, username or channel URL")]
+    InvalidMember { member: String },
+    #[error("No channel found for member {username}")]
+    UnknownMember { username: String },
+    #[error("Invalid source {name}, expected channel:ID, search:QUERY, tag:TAG or trending")]
+    InvalidSource { name: String },
+    #[error("Can't archive {name} incrementally, only channel feeds are ordered newest first")]
+    IncrementalSource { name: String },
+    #[error("Invalid GIF {gif}, expected an ID or GIF URL")]
+    InvalidGif { gif: String },
+    #[error("Download of {url} ended after {actual} of {expected} bytes")]
+    IncompleteDownload {
+        url: String,
+        expected: u64,
+        actual: u64,
+    },
+    #[error("Stored file {path} has {actual} bytes instead of {expected}, remove it and the files linked to it to download them again")]
+    DamagedObject {
+        path: String,
+        expected: u64,
+        actual: u64,
+    },
+    #[error("Invalid link type {name}, expected hardlink or symlink")]
+    InvalidLinkType { name: String },
+    #[error("Invalid template {template}: {reason}")]
+    InvalidTemplate { template: String, reason: String },
+}
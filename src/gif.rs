@@ -0,0 +1,104 @@
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::Result;
+use futures::{stream, StreamExt};
+
+use crate::archive::ArchiveSummary;
+use crate::client::GiphyClient;
+use crate::download::report_error;
+use crate::error::GiphyError;
+use crate::giphy_url::{giphy_url, path_segments};
+use crate::types::GiphyGif;
+
+/// A GIF given by ID or URL
+///
+/// Accepts `aBcDeF123`, `https://giphy.com/gifs/title-words-aBcDeF123`,
+/// `giphy.com/embed/aBcDeF123` and media URLs like
+/// `https://media.giphy.com/media/aBcDeF123/giphy.gif`,
+/// `https://media4.giphy.com/media/v1.<token>/aBcDeF123/giphy.gif` and
+/// `https://i.giphy.com/aBcDeF123.gif`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct GifId(pub String);
+
+impl FromStr for GifId {
+    type Err = GiphyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || GiphyError::InvalidGif { gif: s.to_owned() };
+        let s = s.trim();
+
+        // Giphy GIF page, embed or media URL, the scheme is optional
+        let id = match s.contains('/') {
+            true => {
+                let url = giphy_url(s).ok_or_else(invalid)?;
+                let segments = path_segments(&url);
+                match (url.host_str(), segments.as_slice()) {
+                    (_, ["gifs", slug, ..]) => {
+                        slug.rsplit('-').next().unwrap_or_default().to_owned()
+                    }
+                    // Signed media URLs have a `v1.<token>` segment first
+                    (_, ["media", token, id, ..]) if token.starts_with("v1.") => id.to_string(),
+                    (_, ["embed" | "media", id, ..]) => id.to_string(),
+                    (Some("i.giphy.com"), [file]) => {
+                        file.split('.').next().unwrap_or_default().to_owned()
+                    }
+                    _ => return Err(invalid()),
+                }
+            }
+            false => s.to_owned(),
+        };
+
+        match !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric()) {
+            true => Ok(Self(id)),
+            false => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for GifId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl GiphyClient {
+    /// Fetch a single GIF
+    pub async fn gif(&self, id: &GifId) -> Result<GiphyGif> {
+        let url = self.api_url(&format!("api/v4/gifs/{}", id))?;
+        let text = self.get_text(&url).await?;
+        let gif = GiphyGif::from_value(serde_json::from_str(&text)?)?;
+        if let Some(catalog) = &self.catalog {
+            catalog.record_gifs([&gif])?;
+        }
+        Ok(gif)
+    }
+
+    /// Fetch and download individual GIFs into `dir`, reporting failures
+    /// without aborting
+    pub async fn download_gifs(&self, ids: &[GifId], dir: impl AsRef<Path>) -> ArchiveSummary {
+        let dir = dir.as_ref();
+        let mut results = stream::iter(ids)
+            .map(|id| async move {
+                let gif = self
+                    .gif(id)
+                    .await
+                    .map_err(|e| e.context(format!("id: {}", id)))?;
+                self.download_gif(gif, dir).await
+            })
+            .buffer_unordered(self.concurrency);
+
+        let mut summary = ArchiveSummary::default();
+        while let Some(result) = results.next().await {
+            match result {
+                Ok(()) => summary.archived += 1,
+                Err(e) => {
+                    report_error(&e);
+                    summary.failed += 1;
+                }
+            }
+        }
+        summary
+    }
+}
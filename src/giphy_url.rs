@@ -0,0 +1,23 @@
+use reqwest::Url;
+
+/// Parse a URL on `giphy.com` or one of its subdomains, the scheme is
+/// optional
+pub(crate) fn giphy_url(s: &str) -> Option<Url> {
+    let url = match s.contains("://") {
+        true => Url::parse(s),
+        false => Url::parse(&format!("https://{}", s)),
+    }
+    .ok()?;
+    let host = url.host_str()?;
+    match host == "giphy.com" || host.ends_with(".giphy.com") {
+        true => Some(url),
+        false => None,
+    }
+}
+
+/// Non-empty segments of a URL's path
+pub(crate) fn path_segments(url: &Url) -> Vec<&str> {
+    url.path_segments()
+        .map(|s| s.filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::error::GiphyError;
+
+/// A single rendition of a GIF
+///
+/// Numeric fields are sent as strings by Giphy and parsed into numbers,
+/// fields that aren't modeled are kept in `other`.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct Rendition {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mp4: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webp: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub width: Option<u32>,
+    #[serde(
+        default,
+        deserialize_with = "number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub height: Option<u32>,
+    #[serde(
+        default,
+        deserialize_with = "number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub size: Option<u64>,
+    #[serde(
+        default,
+        deserialize_with = "number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub mp4_size: Option<u64>,
+    #[serde(
+        default,
+        deserialize_with = "number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub webp_size: Option<u64>,
+    #[serde(
+        default,
+        deserialize_with = "number",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub frames: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    #[serde(flatten)]
+    pub other: BTreeMap<String, serde_json::Value>,
+}
+
+impl Rendition {
+    /// URL of a format of this rendition, `None` for the first of `url`,
+    /// `mp4` and `webp` that is present
+    pub fn url(&self, format: Option<&str>) -> Option<&str> {
+        match format {
+            None => present(&self.url)
+                .or_else(|| present(&self.mp4))
+                .or_else(|| present(&self.webp)),
+            Some("url") => present(&self.url),
+            Some("mp4") => present(&self.mp4),
+            Some("webp") => present(&self.webp),
+            Some(f) => self
+                .other
+                .get(f)
+                .and_then(|u| u.as_str())
+                .filter(|u| !u.is_empty()),
+        }
+    }
+
+    /// Byte size of a format of this rendition as listed by Giphy, formats
+    /// are chosen like in [`Rendition::url`]
+    pub fn size(&self, format: Option<&str>) -> Option<u64> {
+        match format {
+            None if self.url(Some("url")).is_some() => self.size,
+            None if self.url(Some("mp4")).is_some() => self.mp4_size,
+            None => self.webp_size,
+            Some("url") => self.size,
+            Some("mp4") => self.mp4_size,
+            Some("webp") => self.webp_size,
+            Some(_) => None,
+        }
+    }
+
+    /// Number of pixels of a frame, if the dimensions are known
+    pub fn pixels(&self) -> Option<u64> {
+        Some(self.width? as u64 * self.height? as u64)
+    }
+}
+
+/// Non-empty URL
+fn present(url: &Option<String>) -> Option<&str> {
+    url.as_deref().filter(|u| !u.is_empty())
+}
+
+/// Parse a number that may be sent as a string, empty strings and values
+/// that aren't numbers are `None`
+fn number<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+{
+    Ok(
+        match Option::<serde_json::Value>::deserialize(deserializer)? {
+            Some(serde_json::Value::Number(n)) => n.to_string().parse().ok(),
+            Some(serde_json::Value::String(s)) => s.parse().ok(),
+            _ => None,
+        },
+    )
+}
+
+/// Parse a rendition, a value that isn't a valid rendition is `None`
+fn rendition<'de, D>(deserializer: D) -> Result<Option<Rendition>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(Rendition::deserialize(value).ok())
+}
+
+/// A rendition that isn't modeled in [`Images`], values that aren't
+/// renditions are kept as they were sent
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum OtherRendition {
+    Rendition(Rendition),
+    Unknown(serde_json::Value),
+}
+
+impl OtherRendition {
+    pub fn rendition(&self) -> Option<&Rendition> {
+        match self {
+            Self::Rendition(r) => Some(r),
+            Self::Unknown(_) => None,
+        }
+    }
+}
+
+macro_rules! images {
+    ($($name:ident),* $(,)?) => {
+        /// Renditions of a GIF by name, renditions that aren't modeled are
+        /// kept in `other` and malformed renditions are skipped
+        #[derive(Serialize, Deserialize, Clone, Default, Debug)]
+        pub struct Images {
+            $(
+                #[serde(
+                    default,
+                    deserialize_with = "rendition",
+                    skip_serializing_if = "Option::is_none"
+                )]
+                pub $name: Option<Rendition>,
+            )*
+            #[serde(flatten)]
+            pub other: BTreeMap<String, OtherRendition>,
+        }
+
+        impl Images {
+            /// Rendition by its Giphy name
+            pub fn get(&self, name: &str) -> Option<&Rendition> {
+                match name {
+                    $(stringify!($name) => self.$name.as_ref(),)*
+                    _ => self.other.get(name).and_then(OtherRendition::rendition),
+                }
+            }
+
+            /// All renditions with their names
+            pub fn iter(&self) -> impl Iterator<Item = (&str, &Rendition)> {
+                let named = [$((stringify!($name), self.$name.as_ref()),)*];
+                named
+                    .into_iter()
+                    .filter_map(|(name, r)| r.map(|r| (name, r)))
+                    .chain(self.other.iter().filter_map(|(name, r)| {
+                        r.rendition().map(|r| (name.as_str(), r))
+                    }))
+            }
+        }
+    };
+}
+
+images!(
+    source,
+    original,
+    original_mp4,
+    original_still,
+    downsized,
+    downsized_large,
+    downsized_medium,
+    downsized_small,
+    downsized_still,
+    fixed_height,
+    fixed_height_downsampled,
+    fixed_height_small,
+    fixed_height_small_still,
+    fixed_height_still,
+    fixed_width,
+    fixed_width_downsampled,
+    fixed_width_small,
+    fixed_width_small_still,
+    fixed_width_still,
+    hd,
+    looping,
+    preview,
+    preview_gif,
+    preview_webp,
+);
+
+impl Images {
+    /// Rendition by its Giphy name, failing if the GIF doesn't have it
+    pub fn rendition(&self, name: &str) -> Result<&Rendition, GiphyError> {
+        self.get(name).ok_or_else(|| GiphyError::MissingRendition {
+            wanted: name.to_owned(),
+        })
+    }
+}
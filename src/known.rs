@@ -0,0 +1,82 @@
+use std::collections::HashSet;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use anyhow::Result;
+use tokio::fs;
+
+use crate::manifest::Manifest;
+use crate::partial::is_partial;
+use crate::types::GiphyGif;
+
+/// GIFs already present in a local archive
+#[derive(Default, Debug)]
+pub(crate) struct KnownGifs {
+    ids: HashSet<String>,
+    index_ids: HashSet<u64>,
+}
+
+impl KnownGifs {
+    /// Collect GIFs from manifests and downloaded files anywhere below `dir`
+    ///
+    /// File names start with `{..}_{index_id:012}_{id}.`, anything else
+    /// including partial downloads and sidecars is ignored.
+    pub(crate) async fn scan(dir: impl AsRef<Path>) -> Result<Self> {
+        let mut known = Self::default();
+        let mut dirs = vec![dir.as_ref().to_owned()];
+
+        while let Some(dir) = dirs.pop() {
+            let mut entries = match fs::read_dir(&dir).await {
+                Ok(d) => d,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            while let Some(entry) = entries.next_entry().await? {
+                if entry.file_type().await?.is_dir() {
+                    dirs.push(entry.path());
+                    continue;
+                }
+                let name = entry.file_name();
+                if name == Manifest::FILE_NAME {
+                    let manifest = Manifest::load(&dir).await?;
+                    for entry in manifest.entries() {
+                        if !dir.join(&entry.filename).exists() {
+                            continue;
+                        }
+                        known.ids.insert(entry.id.clone());
+                        known.index_ids.extend(entry.index_id);
+                    }
+                    continue;
+                }
+                let name = match name.to_str() {
+                    Some(n) if !n.starts_with('.') && !n.ends_with(".json") && !is_partial(n) => n,
+                    _ => continue,
+                };
+                let stem = match name.split_once('.') {
+                    Some((stem, _)) => stem,
+                    None => continue,
+                };
+                let mut parts = stem.rsplit('_');
+                if let (Some(id), Some(index_id)) = (parts.next(), parts.next()) {
+                    if let Ok(index_id) = index_id.parse() {
+                        known.ids.insert(id.to_owned());
+                        // GIFs without an index ID are named with 0
+                        if index_id != 0 {
+                            known.index_ids.insert(index_id);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(known)
+    }
+
+    pub(crate) fn insert(&mut self, id: &str) {
+        self.ids.insert(id.to_owned());
+    }
+
+    pub(crate) fn contains(&self, gif: &GiphyGif) -> bool {
+        self.ids.contains(&gif.id) || gif.index_id.is_some_and(|i| self.index_ids.contains(&i))
+    }
+}
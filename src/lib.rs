@@ -0,0 +1,48 @@
+mod archive;
+mod catalog;
+mod checkpoint;
+mod client;
+mod digest;
+mod download;
+mod error;
+mod gif;
+mod giphy_url;
+mod images;
+mod known;
+mod limit;
+mod manifest;
+mod member;
+mod partial;
+mod rendition;
+mod retry;
+mod sanitize;
+mod sidecar;
+mod source;
+mod stats;
+mod store;
+mod template;
+mod types;
+mod verify;
+
+pub use archive::{ArchiveOptions, ArchiveSummary};
+pub use catalog::{Catalog, CatalogFailure, CatalogFile, CatalogGif};
+pub use checkpoint::{Checkpoint, GifStatus};
+pub use client::{
+    GiphyClient, GiphyClientBuilder, Verbosity, DEFAULT_BASE_URL, DEFAULT_CONCURRENCY,
+};
+pub use digest::{digest_file, FileDigest};
+pub use error::GiphyError;
+pub use gif::GifId;
+pub use images::{Images, OtherRendition, Rendition};
+pub use manifest::{Manifest, ManifestEntry};
+pub use member::MemberRef;
+pub use rendition::{RenditionLayout, RenditionSelection, RenditionSpec};
+pub use retry::RetryPolicy;
+pub use sanitize::{sanitize_component, sanitize_path, MAX_COMPONENT_BYTES};
+pub use sidecar::{DownloadInfo, Sidecar};
+pub use source::Source;
+pub use stats::{archive_stats, ArchiveStats, FileTotals};
+pub use store::{ContentStore, StoreLink};
+pub use template::Template;
+pub use types::{GiphyChannel, GiphyGif, GiphyResponse, GiphyUser};
+pub use verify::{verify_archive, FileIssue, FileProblem, VerifyReport};
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use reqwest::Url;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Caps the number of concurrent connections to each host
+#[derive(Clone, Debug, Default)]
+pub(crate) struct HostLimits {
+    max: Option<usize>,
+    hosts: Arc<Mutex<HashMap<String, Arc<Semaphore>>>>,
+}
+
+impl HostLimits {
+    pub(crate) fn new(max: Option<usize>) -> Self {
+        Self {
+            max: max.map(|m| m.max(1)),
+            hosts: Default::default(),
+        }
+    }
+
+    /// Wait for a connection slot to the host of `url`, held until the
+    /// permit is dropped, `None` if hosts are unlimited
+    pub(crate) async fn acquire(&self, url: &Url) -> Option<OwnedSemaphorePermit> {
+        let max = self.max?;
+        let host = url.host_str().unwrap_or_default().to_owned();
+        let semaphore = self
+            .hosts
+            .lock()
+            .unwrap()
+            .entry(host)
+            .or_insert_with(|| Arc::new(Semaphore::new(max)))
+            .clone();
+        semaphore.acquire_owned().await.ok()
+    }
+}
+
+/// Token bucket limiting a rate shared by clones of a client
+#[derive(Clone, Debug)]
+pub(crate) struct TokenBucket {
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// Tokens added per second
+    rate: f64,
+    /// Most tokens that can accumulate while idle
+    capacity: f64,
+    /// Available tokens, negative while callers wait for their share
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    /// Bucket refilling `rate` tokens per second, starting full
+    pub(crate) fn new(rate: f64, capacity: f64) -> Self {
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket {
+                rate,
+                capacity,
+                tokens: capacity,
+                last: Instant::now(),
+            })),
+        }
+    }
+
+    /// Take `n` tokens, waiting until the bucket has refilled enough
+    ///
+    /// Tokens are reserved immediately, so concurrent callers wait in turn.
+    pub(crate) async fn take(&self, n: f64) {
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap();
+            let now = Instant::now();
+            let refill = now.duration_since(bucket.last).as_secs_f64() * bucket.rate;
+            bucket.tokens = (bucket.tokens + refill).min(bucket.capacity) - n;
+            bucket.last = now;
+            match bucket.tokens < 0.0 {
+                true => Duration::from_secs_f64(-bucket.tokens / bucket.rate),
+                false => Duration::ZERO,
+            }
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
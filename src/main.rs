@@ -1,181 +1,494 @@
-use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use anyhow::{bail, Context, Result};
-use clap::Parser;
-use futures::StreamExt;
-use serde::Deserialize;
-use thiserror::Error;
-use tokio::fs;
-use tokio::io::AsyncWriteExt;
+use clap::{Args, Parser, Subcommand};
+use futures::{StreamExt, TryStreamExt};
+use giphy_download::{
+    archive_stats, verify_archive, ArchiveOptions, Catalog, ContentStore, GifId, GiphyClient,
+    GiphyClientBuilder, GiphyError, MemberRef, RenditionLayout, RenditionSelection, RenditionSpec,
+    RetryPolicy, Source, StoreLink, Template, Verbosity, DEFAULT_BASE_URL, DEFAULT_CONCURRENCY,
+};
 
 #[derive(Parser, Debug)]
-struct Args {
-    /// Giphy member ID
-    #[clap(short, long)]
-    member: u64,
+struct Cli {
+    #[clap(subcommand)]
+    command: Command,
 
+    #[clap(flatten)]
+    global: GlobalArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Archive the feeds of members and other sources
+    Sync {
+        #[clap(flatten)]
+        sources: SourceArgs,
+
+        /// Resume from the checkpoint saved by a previous run
+        #[clap(long)]
+        resume: bool,
+
+        /// Stop after N consecutive GIFs that are already archived, only for
+        /// channel feeds
+        #[clap(
+            long,
+            value_name = "N",
+            min_values = 0,
+            max_values = 1,
+            multiple_values = false,
+            default_missing_value = "25"
+        )]
+        incremental: Option<usize>,
+
+        #[clap(flatten)]
+        download: DownloadArgs,
+    },
+    /// Print the GIFs of feeds without downloading them, one per line
+    List {
+        #[clap(flatten)]
+        sources: SourceArgs,
+
+        /// Stop after N GIFs of each source
+        #[clap(long, value_name = "N")]
+        limit: Option<usize>,
+
+        /// Print the full feed entries as JSON lines
+        #[clap(long)]
+        json: bool,
+    },
+    /// Download individual GIFs
+    Get {
+        /// GIF ID or giphy.com URL
+        #[clap(required = true)]
+        gifs: Vec<GifId>,
+
+        #[clap(flatten)]
+        download: DownloadArgs,
+    },
+    /// Check archived files against the sizes and SHA-256 digests recorded
+    /// in their manifests
+    Verify,
+    /// Summarize the files in the archive
+    Stats,
+}
+
+// Options shared by all subcommands
+#[derive(Args, Debug)]
+struct GlobalArgs {
     /// Download directory
-    #[clap(short, long)]
-    directory: PathBuf,
+    #[clap(short, long, global = true)]
+    directory: Option<PathBuf>,
+
+    /// Giphy API base URL
+    #[clap(long, global = true, env = "GIPHY_BASE_URL", default_value = DEFAULT_BASE_URL)]
+    base_url: String,
+
+    /// Maximum attempts per request
+    #[clap(long, global = true, default_value_t = 5)]
+    max_attempts: u32,
+
+    /// Initial delay between retries in milliseconds
+    #[clap(long, global = true, value_name = "MS", default_value_t = 1000)]
+    retry_delay: u64,
+
+    /// Disable random jitter of retry delays
+    #[clap(long, global = true)]
+    no_retry_jitter: bool,
+
+    /// Maximum number of GIFs downloaded at the same time across all sources
+    #[clap(short, long, global = true, alias = "concurrency", value_name = "N", default_value_t = DEFAULT_CONCURRENCY)]
+    jobs: usize,
+
+    /// Maximum number of concurrent connections to each host
+    #[clap(long, global = true, value_name = "N")]
+    connections_per_host: Option<usize>,
+
+    /// Maximum number of feed and API requests per second
+    #[clap(long, global = true, value_name = "N")]
+    requests_per_second: Option<f64>,
+
+    /// Maximum download bandwidth shared by all downloads in bytes per
+    /// second, with an optional K, M or G suffix
+    #[clap(long, global = true, value_name = "BYTES", parse(try_from_str = parse_bytes))]
+    limit_rate: Option<u64>,
+
+    /// Fetch the next feed page while the current one is downloaded
+    #[clap(long, global = true)]
+    prefetch: bool,
+
+    /// SQLite database recording fetched GIFs and downloads
+    #[clap(long, global = true, value_name = "PATH")]
+    catalog: Option<PathBuf>,
+
+    /// Also print files that are skipped because they're already archived
+    #[clap(short, long, global = true, conflicts_with = "quiet")]
+    verbose: bool,
+
+    /// Only print failures and summaries
+    #[clap(short, long, global = true)]
+    quiet: bool,
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let args = Args::parse();
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(30))
-        .build()?;
-    let gifs = gifs(&client, args.member).await?;
-    download(&client, gifs, args.directory).await?;
+/// Feeds to archive or list
+#[derive(Args, Debug)]
+struct SourceArgs {
+    /// Giphy member ID, username or channel URL, may be repeated
+    #[clap(short, long, multiple_occurrences = true)]
+    member: Vec<MemberRef>,
 
-    Ok(())
+    /// File with one member per line, `#` starts a comment
+    #[clap(long, value_name = "PATH")]
+    members_file: Option<PathBuf>,
+
+    /// Another feed, `search:QUERY`, `tag:TAG`, `trending` or `channel:ID`,
+    /// may be repeated
+    #[clap(long, multiple_occurrences = true)]
+    source: Vec<Source>,
+}
+
+/// What is downloaded and where it's stored
+#[derive(Args, Debug)]
+struct DownloadArgs {
+    /// Renditions to download in order of preference,
+    /// e.g. `source,original_mp4,fixed_height:webp`
+    #[clap(long, use_value_delimiter = true, default_value = "source")]
+    rendition: Vec<RenditionSpec>,
+
+    /// Download every available rendition instead of only the first
+    #[clap(long)]
+    all_renditions: bool,
+
+    /// Where files of different renditions are stored
+    #[clap(
+        long,
+        possible_values = ["subfolder", "suffix"],
+        default_value = "subfolder",
+        requires = "all-renditions"
+    )]
+    rendition_layout: RenditionLayout,
+
+    /// File name of downloads, with fields {id}, {index_id}, {username},
+    /// {user_id}, {title}, {date}, {year}, {month}, {day}, {rendition}
+    /// and {ext}
+    #[clap(long, default_value = Template::DEFAULT_FILENAME)]
+    filename_template: Template,
+
+    /// Directory of downloads below the download directory, with the same
+    /// fields as --filename-template
+    #[clap(long, default_value = Template::DEFAULT_DIR)]
+    dir_template: Template,
+
+    /// Write a `<filename>.json` metadata sidecar next to every downloaded file
+    #[clap(long)]
+    sidecar: bool,
+
+    /// Save each downloaded file once in this directory, named by its
+    /// SHA-256, and link to it from the member directories
+    #[clap(long, value_name = "PATH")]
+    content_store: Option<PathBuf>,
+
+    /// How member directories link to the content store
+    #[clap(long, possible_values = ["hardlink", "symlink"], default_value = "hardlink")]
+    store_link: StoreLink,
 }
 
-#[derive(Deserialize, Debug)]
-struct GiphyResponse {
-    next: Option<String>,
-    results: Vec<GiphyGif>,
-}
-
-#[allow(dead_code)]
-#[derive(Deserialize, Debug)]
-struct GiphyGif {
-    id: String,
-    index_id: u64,
-    images: HashMap<String, serde_json::Value>,
-    title: String,
-    user: GiphyUser,
-    #[serde(rename = "create_datetime")]
-    create_time: String,
-}
-
-#[allow(dead_code)]
-#[derive(Deserialize, Debug)]
-struct GiphyUser {
-    id: u64,
-    name: String,
-    username: String,
-}
-
-#[derive(Error, Debug)]
-enum GiphyError {
-    #[error("Received response error status {code} for url {url}")]
-    ResponseError { code: u16, url: String },
-    #[error("Invalid source video found")]
-    InvalidSourceVideo,
-    #[error("Invalid date {date}")]
-    InvalidTime { date: String },
-}
-
-async fn gifs(client: &reqwest::Client, member_id: u64) -> Result<Vec<GiphyGif>> {
-    let mut gifs = Vec::new();
-    let mut url = format!("https://giphy.com/api/v4/channels/{}/feed", member_id);
-
-    for i in 1.. {
-        println!("Fetching page {}", i);
-
-        // Query GIFs
-        let resp = client.get(&url).send().await?;
-        if !resp.status().is_success() {
-            bail!(GiphyError::ResponseError {
-                code: resp.status().as_u16(),
-                url: url.clone(),
-            });
+impl GlobalArgs {
+    /// Download directory, which every subcommand but `list` needs
+    fn directory(&self) -> Result<&Path> {
+        self.directory
+            .as_deref()
+            .context("Missing --directory, the download directory")
+    }
+
+    fn verbosity(&self) -> Verbosity {
+        match (self.quiet, self.verbose) {
+            (true, _) => Verbosity::Quiet,
+            (_, true) => Verbosity::Verbose,
+            _ => Verbosity::Normal,
         }
+    }
 
-        // Append GIFs
-        let text = resp.text().await?;
-        let mut giphy_resp: GiphyResponse = serde_json::from_str(&text)?;
-        gifs.append(&mut giphy_resp.results);
+    /// Client builder with the network and catalog settings
+    fn client(&self) -> Result<GiphyClientBuilder> {
+        let retry = RetryPolicy {
+            max_attempts: self.max_attempts,
+            base_delay: Duration::from_millis(self.retry_delay),
+            jitter: !self.no_retry_jitter,
+            ..Default::default()
+        };
+        let mut builder = GiphyClient::builder()
+            .base_url(&self.base_url)
+            .retry(retry)
+            .concurrency(self.jobs)
+            .prefetch_pages(self.prefetch)
+            .verbosity(self.verbosity());
+        if let Some(connections) = self.connections_per_host {
+            builder = builder.connections_per_host(connections);
+        }
+        if let Some(rate) = self.requests_per_second {
+            builder = builder.requests_per_second(rate);
+        }
+        if let Some(rate) = self.limit_rate {
+            builder = builder.bytes_per_second(rate);
+        }
+        if let Some(path) = &self.catalog {
+            builder = builder.catalog(Catalog::open(path)?);
+        }
+        Ok(builder)
+    }
+}
+
+impl DownloadArgs {
+    /// Add the download settings to a client builder
+    fn apply(self, mut builder: GiphyClientBuilder) -> GiphyClientBuilder {
+        builder = builder
+            .renditions(RenditionSelection {
+                renditions: self.rendition,
+                all: self.all_renditions.then_some(self.rendition_layout),
+            })
+            .filename_template(self.filename_template)
+            .dir_template(self.dir_template)
+            .sidecars(self.sidecar);
+        if let Some(path) = self.content_store {
+            builder = builder.content_store(ContentStore::new(path, self.store_link));
+        }
+        builder
+    }
+}
+
+impl SourceArgs {
+    /// Sources in the given order without duplicates, resolving usernames
+    /// and channel URLs to member IDs
+    async fn resolve(&self, client: &GiphyClient, verbosity: Verbosity) -> Result<Vec<Source>> {
+        let mut members = self.member.clone();
+        if let Some(path) = &self.members_file {
+            members.extend(read_members_file(path)?);
+        }
+        if members.is_empty() && self.source.is_empty() {
+            bail!("No sources given, use --member, --members-file or --source");
+        }
 
-        // Check for more
-        match giphy_resp.next {
-            Some(u) => url = u,
-            None => break,
+        let mut sources = Vec::new();
+        for member in &members {
+            let id = client.resolve_member(member).await?;
+            if let (MemberRef::Username(name), true) = (member, verbosity > Verbosity::Quiet) {
+                eprintln!("Resolved {} to member {}", name, id);
+            }
+            sources.push(Source::Channel(id));
         }
+        sources.extend(self.source.iter().cloned());
+        dedup(&mut sources);
+        Ok(sources)
     }
+}
 
-    Ok(gifs)
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let global = cli.global;
+    match cli.command {
+        Command::Sync {
+            sources,
+            resume,
+            incremental,
+            download,
+        } => {
+            let directory = global.directory()?;
+            let client = download.apply(global.client()?).build()?;
+            let sources = sources.resolve(&client, global.verbosity()).await?;
+            // Check before archiving any source
+            if let (Some(_), Some(source)) = (incremental, sources.iter().find(|s| !s.is_channel()))
+            {
+                bail!(GiphyError::IncrementalSource {
+                    name: source.to_string(),
+                });
+            }
+            let options = ArchiveOptions {
+                resume,
+                incremental,
+            };
+            sync(&client, &sources, directory, &options).await
+        }
+        Command::List {
+            sources,
+            limit,
+            json,
+        } => {
+            // Progress would mix with the listing on stdout
+            let verbosity = match global.verbose {
+                true => Verbosity::Verbose,
+                false => Verbosity::Quiet,
+            };
+            let client = global.client()?.verbosity(verbosity).build()?;
+            let sources = sources.resolve(&client, global.verbosity()).await?;
+            list(&client, &sources, limit, json).await
+        }
+        Command::Get { gifs, download } => {
+            let directory = global.directory()?;
+            let client = download.apply(global.client()?).build()?;
+            let summary = client.download_gifs(&gifs, directory).await;
+            println!("Summary:");
+            println!(
+                "  GIFs: {} archived, {} failed",
+                summary.archived, summary.failed
+            );
+            if summary.failed > 0 {
+                bail!("{} of {} GIFs failed", summary.failed, gifs.len());
+            }
+            Ok(())
+        }
+        Command::Verify => verify(global.directory()?).await,
+        Command::Stats => stats(global.directory()?).await,
+    }
 }
 
-async fn download(
-    client: &reqwest::Client,
-    gifs: Vec<GiphyGif>,
-    dir: impl AsRef<Path>,
+/// Archive every source and print a summary
+async fn sync(
+    client: &GiphyClient,
+    sources: &[Source],
+    directory: &Path,
+    options: &ArchiveOptions,
 ) -> Result<()> {
-    let results =
-        futures::stream::iter(gifs.into_iter().map(|gif| download_gif(client, gif, &dir)))
-            .buffer_unordered(20)
-            .collect::<Vec<_>>()
-            .await;
-    for r in results {
-        if let Err(e) = r {
-            eprintln!("Failed to download {}", e);
-            e.chain().skip(1).for_each(|cause| eprintln!("  {}", cause));
+    let results = client.archive_sources(sources, directory, options).await;
+
+    println!("Summary:");
+    let mut failed_sources = 0;
+    for (source, result) in &results {
+        match result {
+            Ok(summary) => println!(
+                "  {}: {} archived, {} failed",
+                source, summary.archived, summary.failed
+            ),
+            Err(e) => {
+                failed_sources += 1;
+                println!("  {}: error: {:#}", source, e);
+            }
         }
     }
-
+    if failed_sources > 0 {
+        bail!("{} of {} sources failed", failed_sources, results.len());
+    }
     Ok(())
 }
 
-async fn download_gif(
-    client: &reqwest::Client,
-    gif: GiphyGif,
-    base_dir: impl AsRef<Path>,
+/// Print the GIFs of every source, tab separated or as JSON lines
+async fn list(
+    client: &GiphyClient,
+    sources: &[Source],
+    limit: Option<usize>,
+    json: bool,
 ) -> Result<()> {
-    let id = gif.id.clone();
-    _download_gif(client, gif, base_dir)
-        .await
-        .context(format!("id: {}", id))
+    for source in sources {
+        let gifs = client.source_gifs(source).take(limit.unwrap_or(usize::MAX));
+        futures::pin_mut!(gifs);
+        while let Some(gif) = gifs.try_next().await? {
+            match json {
+                true => println!("{}", gif.raw),
+                false => println!(
+                    "{}\t{}\t{}\t{}\t{}",
+                    gif.id,
+                    gif.index_id.map(|i| i.to_string()).unwrap_or_default(),
+                    gif.create_time,
+                    gif.user.as_ref().map_or("", |u| u.username.as_str()),
+                    gif.title
+                ),
+            }
+        }
+    }
+    Ok(())
 }
 
-async fn _download_gif(
-    client: &reqwest::Client,
-    gif: GiphyGif,
-    base_dir: impl AsRef<Path>,
-) -> Result<()> {
-    // Get source url
-    let source_url = gif
-        .images
-        .get("source")
-        .and_then(|x| x.get("url"))
-        .and_then(|x| x.as_str())
-        .ok_or(GiphyError::InvalidSourceVideo)?;
-    let ext = source_url
-        .rsplit_once('.')
-        .ok_or(GiphyError::InvalidSourceVideo)?
-        .1;
-
-    // Generate file name and create directory
-    let date = gif
-        .create_time
-        .split_once('T')
-        .ok_or_else(|| GiphyError::InvalidTime {
-            date: gif.create_time.clone(),
-        })?
-        .0
-        .replace('-', "");
-    let filename = format!(
-        "{}_{}_{:012}_{}.{}",
-        date, &gif.user.username, &gif.index_id, &gif.id, ext
+/// Report missing, truncated and modified files below `dir`
+async fn verify(dir: &Path) -> Result<()> {
+    let report = verify_archive(dir).await?;
+    for issue in &report.issues {
+        println!("{}: {}", issue.path.to_string_lossy(), issue.problem);
+    }
+    println!(
+        "Verified {} files, {} without a recorded SHA-256, {} problems",
+        report.checked,
+        report.unhashed,
+        report.issues.len()
     );
-    let dir = base_dir.as_ref().join(&gif.user.username);
-    fs::create_dir_all(&dir).await?;
-    let path = dir.join(filename);
+    if !report.is_ok() {
+        bail!(
+            "{} of {} files failed verification",
+            report.issues.len(),
+            report.checked
+        );
+    }
+    Ok(())
+}
 
-    // Check if file exists
-    if path.exists() {
-        return Ok(());
+/// Print the number and size of archived files in total and per directory
+async fn stats(dir: &Path) -> Result<()> {
+    let stats = archive_stats(dir).await?;
+    println!(
+        "{} files of {} GIFs, {} bytes",
+        stats.total.files, stats.gifs, stats.total.bytes
+    );
+    if let (Some(oldest), Some(newest)) = (&stats.oldest, &stats.newest) {
+        println!("Created {} to {}", oldest, newest);
     }
+    for (dir, totals) in &stats.dirs {
+        println!(
+            "  {}: {} files, {} bytes",
+            dir.to_string_lossy(),
+            totals.files,
+            totals.bytes
+        );
+    }
+    Ok(())
+}
 
-    // Download
-    let video = client.get(source_url).send().await?.bytes().await?;
-    let mut buffer = fs::File::create(&path).await?;
-    buffer.write_all(&video).await?;
+/// Parse a byte count like `500K` or `2M`, suffixes are powers of 1024
+fn parse_bytes(s: &str) -> Result<u64> {
+    let (number, multiplier) = match s.char_indices().next_back() {
+        Some((i, 'k' | 'K')) => (&s[..i], 1 << 10),
+        Some((i, 'm' | 'M')) => (&s[..i], 1 << 20),
+        Some((i, 'g' | 'G')) => (&s[..i], 1 << 30),
+        _ => (s, 1),
+    };
+    let number: f64 = number.parse().context("Invalid byte count")?;
+    if !number.is_finite() || number < 0.0 {
+        bail!("Invalid byte count");
+    }
+    Ok((number * multiplier as f64) as u64)
+}
 
-    println!("Downloaded {}", path.to_string_lossy());
+/// Remove repeated items, keeping the first occurrence
+fn dedup<T: PartialEq>(items: &mut Vec<T>) {
+    let mut i = 0;
+    while i < items.len() {
+        if items[..i].contains(&items[i]) {
+            items.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+}
 
-    Ok(())
+/// Read members from a file with one ID, username or channel URL per line,
+/// ignoring comments and blank lines
+fn read_members_file(path: &Path) -> Result<Vec<MemberRef>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.to_string_lossy()))?;
+    text.lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            (!line.is_empty()).then(|| {
+                line.parse().with_context(|| {
+                    format!(
+                        "Invalid member {:?} on line {} of {}",
+                        line,
+                        i + 1,
+                        path.to_string_lossy()
+                    )
+                })
+            })
+        })
+        .collect()
 }
@@ -0,0 +1,138 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+use crate::client::GiphyClient;
+
+/// Manifests of member directories shared by concurrent downloads
+pub(crate) type Manifests = Arc<Mutex<HashMap<PathBuf, Arc<Mutex<Manifest>>>>>;
+
+/// An archived file in a member directory
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ManifestEntry {
+    /// Path relative to the member directory
+    pub filename: String,
+    pub id: String,
+    #[serde(default)]
+    pub index_id: Option<u64>,
+    pub title: String,
+    pub create_time: String,
+    pub rendition: String,
+    pub size: Option<u64>,
+    /// Lowercase hex SHA-256, `None` for files archived before the manifest
+    /// was written
+    pub sha256: Option<String>,
+    pub archived_at: DateTime<Utc>,
+}
+
+/// Index of the archived files of a member directory
+///
+/// Stored as JSON lines in `manifest.jsonl` that are appended as files are
+/// archived, later lines replace earlier ones with the same filename.
+#[derive(Debug)]
+pub struct Manifest {
+    path: PathBuf,
+    entries: BTreeMap<String, ManifestEntry>,
+}
+
+impl Manifest {
+    pub const FILE_NAME: &'static str = "manifest.jsonl";
+
+    /// Load the manifest of a member directory, empty if there is none
+    pub async fn load(member_dir: impl AsRef<Path>) -> Result<Self> {
+        let path = member_dir.as_ref().join(Self::FILE_NAME);
+        let text = match fs::read_to_string(&path).await {
+            Ok(t) => t,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        // A crash may leave a partial last line behind
+        let entries = text
+            .lines()
+            .filter_map(|line| serde_json::from_str::<ManifestEntry>(line).ok())
+            .map(|entry| (entry.filename.clone(), entry))
+            .collect();
+        Ok(Self { path, entries })
+    }
+
+    /// Load the manifests of all member directories anywhere below `dir`
+    pub async fn load_all(dir: impl AsRef<Path>) -> Result<Vec<Self>> {
+        let mut manifests = Vec::new();
+        let mut dirs = vec![dir.as_ref().to_owned()];
+        while let Some(dir) = dirs.pop() {
+            let mut entries = match fs::read_dir(&dir).await {
+                Ok(d) => d,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            while let Some(entry) = entries.next_entry().await? {
+                if entry.file_type().await?.is_dir() {
+                    dirs.push(entry.path());
+                } else if entry.file_name() == Self::FILE_NAME {
+                    manifests.push(Self::load(&dir).await?);
+                }
+            }
+        }
+        Ok(manifests)
+    }
+
+    /// Member directory of the manifest
+    pub fn dir(&self) -> &Path {
+        self.path.parent().unwrap_or_else(|| Path::new(""))
+    }
+
+    pub fn get(&self, filename: &str) -> Option<&ManifestEntry> {
+        self.entries.get(filename)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &ManifestEntry> {
+        self.entries.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Add or replace an entry and append it to the manifest file
+    pub async fn insert(&mut self, entry: ManifestEntry) -> Result<()> {
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(&line).await?;
+        file.flush().await?;
+
+        self.entries.insert(entry.filename.clone(), entry);
+        Ok(())
+    }
+}
+
+impl GiphyClient {
+    /// Manifest of a member directory, loaded on first use
+    pub(crate) async fn manifest(&self, member_dir: &Path) -> Result<Arc<Mutex<Manifest>>> {
+        let mut manifests = self.manifests.lock().await;
+        if let Some(manifest) = manifests.get(member_dir) {
+            return Ok(manifest.clone());
+        }
+
+        let manifest = Arc::new(Mutex::new(Manifest::load(member_dir).await?));
+        manifests.insert(member_dir.to_owned(), manifest.clone());
+        Ok(manifest)
+    }
+}
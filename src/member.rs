@@ -0,0 +1,135 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::client::GiphyClient;
+use crate::error::GiphyError;
+use crate::giphy_url::{giphy_url, path_segments};
+use crate::types::GiphyChannel;
+
+/// Top-level paths of giphy.com that are not member profiles
+const RESERVED_PATHS: &[&str] = &[
+    "about",
+    "api",
+    "apps",
+    "artists",
+    "categories",
+    "channel",
+    "clips",
+    "create",
+    "embed",
+    "explore",
+    "favorites",
+    "gifs",
+    "join",
+    "login",
+    "media",
+    "reactions",
+    "search",
+    "settings",
+    "stickers",
+    "stories",
+    "trending",
+    "upload",
+];
+
+/// A member given by numeric ID, username or channel URL
+///
+/// Accepts `1234`, `username`, `giphy.com/channel/username` and
+/// `https://giphy.com/username`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MemberRef {
+    Id(u64),
+    Username(String),
+}
+
+impl FromStr for MemberRef {
+    type Err = GiphyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || GiphyError::InvalidMember {
+            member: s.to_owned(),
+        };
+        let s = s.trim();
+        if let Ok(id) = s.parse() {
+            return Ok(Self::Id(id));
+        }
+
+        // Giphy channel or profile URL, the scheme is optional
+        let username = match s.contains('/') {
+            true => {
+                let url = giphy_url(s).ok_or_else(invalid)?;
+                // Only `/channel/<username>` and `/<username>` are members,
+                // other paths like `/gifs/<slug>` are not
+                match path_segments(&url)[..] {
+                    ["channel", username] => username.to_owned(),
+                    [username] if !RESERVED_PATHS.contains(&username) => username.to_owned(),
+                    _ => return Err(invalid()),
+                }
+            }
+            false => s.to_owned(),
+        };
+
+        let valid = !username.is_empty()
+            && username
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        match valid {
+            true => Ok(Self::Username(username)),
+            false => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for MemberRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "{}", id),
+            Self::Username(name) => f.write_str(name),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ChannelSearch {
+    results: Vec<GiphyChannel>,
+}
+
+impl GiphyClient {
+    /// Member ID of a member reference, looking up usernames with the API
+    pub async fn resolve_member(&self, member: &MemberRef) -> Result<u64> {
+        match member {
+            MemberRef::Id(id) => Ok(*id),
+            MemberRef::Username(name) => Ok(self
+                .channel(name)
+                .await
+                .context(format!("member: {}", name))?
+                .id),
+        }
+    }
+
+    /// Look up the channel of a username
+    pub async fn channel(&self, username: &str) -> Result<GiphyChannel> {
+        let mut url = self.api_url("api/v4/channels/search")?;
+        url.query_pairs_mut().append_pair("q", username);
+        let text = self.get_text(&url).await?;
+        let search: ChannelSearch = serde_json::from_str(&text)?;
+
+        // Search results also contain similar names
+        search
+            .results
+            .into_iter()
+            .find(|c| {
+                c.slug.eq_ignore_ascii_case(username)
+                    || c.user.username.eq_ignore_ascii_case(username)
+            })
+            .ok_or_else(|| {
+                GiphyError::UnknownMember {
+                    username: username.to_owned(),
+                }
+                .into()
+            })
+    }
+}
@@ -0,0 +1,93 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use reqwest::header::{ACCEPT_RANGES, ETAG, LAST_MODIFIED};
+use reqwest::{Response, Url};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+/// Temporary file a download is written to before being renamed to `path`
+pub(crate) fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+/// Whether a file name belongs to an unfinished download
+pub(crate) fn is_partial(name: &str) -> bool {
+    name.ends_with(".part") || name.ends_with(".part.json")
+}
+
+/// Remove the partial download of `path`, if any
+pub(crate) async fn remove_partial(path: &Path) -> Result<()> {
+    let part = part_path(path);
+    for p in [PartInfo::path(&part), part] {
+        if p.exists() {
+            fs::remove_file(p).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Validators of the response a partial download was started from
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct PartInfo {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+impl PartInfo {
+    fn path(part: &Path) -> PathBuf {
+        let mut name = part.file_name().unwrap_or_default().to_owned();
+        name.push(".json");
+        part.with_file_name(name)
+    }
+
+    /// Validators of a full response, `None` if it can't be resumed later
+    pub(crate) fn from_response(url: &Url, resp: &Response) -> Option<Self> {
+        let header = |name| {
+            resp.headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_owned())
+        };
+        if header(ACCEPT_RANGES).as_deref() != Some("bytes") {
+            return None;
+        }
+
+        let info = Self {
+            url: url.to_string(),
+            // Weak ETags can't be used with If-Range
+            etag: header(ETAG).filter(|e| !e.starts_with("W/")),
+            last_modified: header(LAST_MODIFIED),
+        };
+        info.validator().is_some().then_some(info)
+    }
+
+    /// Load the info of a partial download of `url` at `part`
+    pub(crate) async fn load(part: &Path, url: &Url) -> Option<Self> {
+        let text = fs::read_to_string(Self::path(part)).await.ok()?;
+        serde_json::from_str::<Self>(&text)
+            .ok()
+            .filter(|info| info.url == url.as_str())
+    }
+
+    pub(crate) async fn save(&self, part: &Path) -> Result<()> {
+        fs::write(Self::path(part), serde_json::to_vec(self)?).await?;
+        Ok(())
+    }
+
+    pub(crate) async fn remove(part: &Path) -> Result<()> {
+        let path = Self::path(part);
+        if path.exists() {
+            fs::remove_file(path).await?;
+        }
+        Ok(())
+    }
+
+    /// Value for an `If-Range` header
+    pub(crate) fn validator(&self) -> Option<&str> {
+        self.etag.as_deref().or(self.last_modified.as_deref())
+    }
+}
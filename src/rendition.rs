@@ -0,0 +1,132 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::GiphyError;
+use crate::images::Rendition;
+use crate::types::GiphyGif;
+
+/// A rendition of a GIF, e.g. `source`, `original` or `fixed_height:webp`
+///
+/// Without a format the rendition's `url` is used, falling back to its `mp4`
+/// and `webp` variants.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RenditionSpec {
+    pub name: String,
+    /// URL field of the rendition, e.g. `mp4` or `webp`
+    pub format: Option<String>,
+}
+
+impl RenditionSpec {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            format: None,
+        }
+    }
+
+    /// URL of this rendition of `gif`, if available
+    pub fn url<'a>(&self, gif: &'a GiphyGif) -> Option<&'a str> {
+        self.rendition(gif)?.url(self.format.as_deref())
+    }
+
+    /// Byte size of this rendition of `gif` as listed by Giphy, if known
+    pub fn size(&self, gif: &GiphyGif) -> Option<u64> {
+        self.rendition(gif)?.size(self.format.as_deref())
+    }
+
+    /// The rendition of `gif` this refers to, if available
+    pub fn rendition<'a>(&self, gif: &'a GiphyGif) -> Option<&'a Rendition> {
+        gif.images.get(&self.name)
+    }
+
+    /// Label used to tell renditions apart in file names
+    pub fn label(&self) -> String {
+        match &self.format {
+            Some(f) => format!("{}-{}", self.name, f),
+            None => self.name.clone(),
+        }
+    }
+}
+
+impl FromStr for RenditionSpec {
+    type Err = GiphyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, format) = match s.split_once(':') {
+            Some((name, format)) => (name, Some(format)),
+            None => (s, None),
+        };
+        let valid =
+            |p: &str| !p.is_empty() && p.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid(name) || !format.is_none_or(valid) {
+            return Err(GiphyError::InvalidRendition { name: s.to_owned() });
+        }
+        Ok(Self {
+            name: name.to_owned(),
+            format: format.map(|f| f.to_owned()),
+        })
+    }
+}
+
+impl fmt::Display for RenditionSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.format {
+            Some(format) => write!(f, "{}:{}", self.name, format),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// Where files of different renditions of the same GIF are stored
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RenditionLayout {
+    /// One subfolder per rendition in the member directory
+    Subfolder,
+    /// Rendition label added before the file extension
+    Suffix,
+}
+
+impl FromStr for RenditionLayout {
+    type Err = GiphyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "subfolder" => Ok(Self::Subfolder),
+            "suffix" => Ok(Self::Suffix),
+            _ => Err(GiphyError::InvalidLayout { name: s.to_owned() }),
+        }
+    }
+}
+
+/// Which renditions of each GIF are downloaded
+#[derive(Clone, Debug)]
+pub struct RenditionSelection {
+    /// Renditions in order of preference
+    pub renditions: Vec<RenditionSpec>,
+    /// Download every available rendition with this layout instead of only
+    /// the first available one
+    pub all: Option<RenditionLayout>,
+}
+
+impl Default for RenditionSelection {
+    fn default() -> Self {
+        Self {
+            renditions: vec![RenditionSpec::new("source")],
+            all: None,
+        }
+    }
+}
+
+impl RenditionSelection {
+    /// Renditions of `gif` to download with their URLs
+    pub fn select<'a>(&'a self, gif: &'a GiphyGif) -> Vec<(&'a RenditionSpec, &'a str)> {
+        let available = self
+            .renditions
+            .iter()
+            .filter_map(|spec| spec.url(gif).map(|url| (spec, url)));
+        match self.all {
+            Some(_) => available.collect(),
+            None => available.take(1).collect(),
+        }
+    }
+}
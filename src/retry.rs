@@ -0,0 +1,174 @@
+use std::future::Future;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{StatusCode, Url};
+
+use crate::client::{GiphyClient, Verbosity};
+use crate::error::GiphyError;
+
+/// How failed requests are retried
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Total number of attempts per request, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for every further retry
+    pub base_delay: Duration,
+    /// Upper bound for any single delay, including `Retry-After`
+    pub max_delay: Duration,
+    /// Randomize each backoff delay between half and all of its value
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Delay before retrying after `attempt` failed attempts
+    fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        let delay = match retry_after {
+            Some(d) => d,
+            None => {
+                let backoff = self
+                    .base_delay
+                    .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+                match self.jitter {
+                    true => backoff.mul_f64(rand::thread_rng().gen_range(0.5..=1.0)),
+                    false => backoff,
+                }
+            }
+        };
+        delay.min(self.max_delay)
+    }
+}
+
+/// Whether a failed request may succeed when retried
+fn is_retryable(e: &anyhow::Error) -> bool {
+    if let Some(GiphyError::ResponseError { code, .. }) = e.downcast_ref() {
+        return is_retryable_status(*code);
+    }
+    if let Some(GiphyError::IncompleteDownload { .. }) = e.downcast_ref() {
+        return true;
+    }
+    if let Some(e) = e.downcast_ref::<reqwest::Error>() {
+        return match e.status() {
+            Some(status) => is_retryable_status(status.as_u16()),
+            None => e.is_timeout() || e.is_connect() || e.is_request() || e.is_body(),
+        };
+    }
+    false
+}
+
+fn is_retryable_status(code: u16) -> bool {
+    matches!(code, 408 | 425 | 429) || (500..600).contains(&code)
+}
+
+/// Delay requested by a `Retry-After` header on 429 and 503 responses
+pub(crate) fn retry_after(status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+    if status != StatusCode::TOO_MANY_REQUESTS && status != StatusCode::SERVICE_UNAVAILABLE {
+        return None;
+    }
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    match value.parse() {
+        Ok(secs) => Some(Duration::from_secs(secs)),
+        Err(_) => httpdate::parse_http_date(value)
+            .ok()?
+            .duration_since(SystemTime::now())
+            .ok(),
+    }
+}
+
+impl GiphyClient {
+    /// Run a request to `url`, retrying transient failures according to the
+    /// client's retry policy
+    pub(crate) async fn with_retry<T, F, Fut>(&self, url: &Url, mut f: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let policy = &self.retry;
+        let mut attempt = 1;
+        loop {
+            let e = match f().await {
+                Ok(t) => return Ok(t),
+                Err(e) => e,
+            };
+            if attempt >= policy.max_attempts || !is_retryable(&e) {
+                return Err(e);
+            }
+
+            let retry_after = match e.downcast_ref() {
+                Some(GiphyError::ResponseError { retry_after, .. }) => *retry_after,
+                _ => None,
+            };
+            let delay = policy.delay(attempt, retry_after);
+            if self.prints(Verbosity::Normal) {
+                eprintln!(
+                    "Retrying {} in {:.1}s (attempt {}/{}): {}",
+                    url,
+                    delay.as_secs_f64(),
+                    attempt + 1,
+                    policy.max_attempts,
+                    e
+                );
+            }
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Fetch the body of an API `url` as text, with retries and the
+    /// client's request rate limit
+    pub(crate) async fn get_text(&self, url: &Url) -> Result<String> {
+        self.with_retry(url, || async {
+            if let Some(rate) = &self.request_rate {
+                rate.take(1.0).await;
+            }
+            let _permit = self.host_limits.acquire(url).await;
+            Ok(self.get(url).await?.text().await?)
+        })
+        .await
+    }
+
+    /// Send a GET request, failing on unsuccessful status codes
+    pub(crate) async fn get(&self, url: &Url) -> Result<reqwest::Response> {
+        self.send(self.client.get(url.clone()), url).await
+    }
+
+    /// Send a request to `url`, failing on unsuccessful status codes
+    pub(crate) async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+        url: &Url,
+    ) -> Result<reqwest::Response> {
+        let resp = request.send().await?;
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(GiphyError::ResponseError {
+                code: status.as_u16(),
+                url: url.to_string(),
+                retry_after: retry_after(status, resp.headers()),
+            }
+            .into());
+        }
+        Ok(resp)
+    }
+}
@@ -0,0 +1,101 @@
+use unicode_normalization::UnicodeNormalization;
+
+/// Longest path component in bytes, leaving room for the `.part.json` and
+/// `.json` files written next to downloads within the common 255 byte limit
+pub const MAX_COMPONENT_BYTES: usize = 240;
+
+/// Longest extension kept when a component is truncated
+const MAX_EXTENSION_BYTES: usize = 16;
+
+/// Device names Windows and SMB shares refuse, with or without an extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Make a value from the feed safe to use inside a path component
+///
+/// Normalizes to NFC, drops invisible formatting characters and replaces
+/// path separators, control characters and characters that are invalid on
+/// Windows with `_`.
+pub(crate) fn sanitize_field(value: &str) -> String {
+    value
+        .nfc()
+        .filter(|c| !is_invisible(*c))
+        .map(|c| match c {
+            '/' | '\\' | '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        .trim()
+        .to_owned()
+}
+
+/// Make a single file or directory name safe on common filesystems
+///
+/// Besides [`sanitize_field`], trailing dots and spaces are removed, `.`,
+/// `..`, empty and reserved names are replaced and the name is truncated to
+/// [`MAX_COMPONENT_BYTES`] while keeping its extension.
+pub fn sanitize_component(name: &str) -> String {
+    let name = sanitize_field(name);
+    let name = name.trim_end_matches(['.', ' ']);
+    if name.is_empty() {
+        return "_".to_owned();
+    }
+
+    let stem = name.split('.').next().unwrap_or_default();
+    let name = match RESERVED_NAMES
+        .iter()
+        .any(|r| r.eq_ignore_ascii_case(stem.trim_end()))
+    {
+        true => format!("_{}", name),
+        false => name.to_owned(),
+    };
+    truncate(&name, MAX_COMPONENT_BYTES)
+}
+
+/// Sanitize every component of a `/` separated relative path
+///
+/// Empty components are dropped, so the result never has a root, `.` or
+/// `..` component and always stays inside the directory it's joined to. A
+/// path without components becomes `_`.
+pub fn sanitize_path(path: &str) -> String {
+    let components: Vec<_> = path
+        .split(['/', '\\'])
+        .filter(|c| !c.is_empty())
+        .map(sanitize_component)
+        .collect();
+    match components.is_empty() {
+        true => "_".to_owned(),
+        false => components.join("/"),
+    }
+}
+
+/// Truncate `name` to at most `max` bytes on a character boundary, keeping a
+/// short extension
+pub(crate) fn truncate(name: &str, max: usize) -> String {
+    if name.len() <= max {
+        return name.to_owned();
+    }
+
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && ext.len() < MAX_EXTENSION_BYTES => {
+            (stem, &name[stem.len()..])
+        }
+        _ => (name, ""),
+    };
+    let mut end = max - ext.len();
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}", stem[..end].trim_end_matches(['.', ' ']), ext)
+}
+
+/// Zero width and bidirectional formatting characters
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' | '\u{FEFF}'
+    )
+}
@@ -0,0 +1,44 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+/// Metadata written next to a downloaded file
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Sidecar {
+    /// Feed entry of the GIF as Giphy sent it
+    pub gif: serde_json::Value,
+    pub download: DownloadInfo,
+}
+
+/// Facts about a downloaded file
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DownloadInfo {
+    pub rendition: String,
+    pub source_url: String,
+    pub size: u64,
+    pub sha256: String,
+    pub downloaded_at: DateTime<Utc>,
+}
+
+impl Sidecar {
+    /// Location of the sidecar of a downloaded file, `<filename>.json`
+    pub fn path(file: impl AsRef<Path>) -> PathBuf {
+        let file = file.as_ref();
+        let mut name = file.file_name().unwrap_or_default().to_owned();
+        name.push(".json");
+        file.with_file_name(name)
+    }
+
+    pub async fn load(file: impl AsRef<Path>) -> Result<Self> {
+        let text = fs::read_to_string(Self::path(file)).await?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    pub async fn save(&self, file: impl AsRef<Path>) -> Result<()> {
+        fs::write(Self::path(file), serde_json::to_vec_pretty(self)?).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::Result;
+use futures::{stream, Stream, TryStreamExt};
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+
+use crate::checkpoint::Checkpoint;
+use crate::client::GiphyClient;
+use crate::error::GiphyError;
+use crate::sanitize::{sanitize_component, truncate};
+use crate::types::{GiphyGif, GiphyResponse};
+
+/// A paginated feed of GIFs that can be archived
+///
+/// Parsed from `channel:1234`, `search:<query>`, `tag:<tag>` or `trending`.
+/// Sub-collections of a channel are channels with their own ID.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Source {
+    /// A member's channel or one of its sub-collections
+    Channel(u64),
+    /// Results of a search query
+    Search(String),
+    /// GIFs with a tag
+    Tag(String),
+    /// The trending feed
+    Trending,
+}
+
+impl Source {
+    /// Name of the source's state files, the member ID for channels
+    ///
+    /// Queries and tags are sanitized and prefixed with a short hash of the
+    /// original, so queries that only differ in replaced characters don't
+    /// share state.
+    pub fn key(&self) -> String {
+        match self {
+            Self::Channel(id) => id.to_string(),
+            Self::Search(query) => hashed_key("search", query),
+            Self::Tag(tag) => hashed_key("tag", tag),
+            Self::Trending => "trending".to_owned(),
+        }
+    }
+
+    /// Whether the source is a channel feed, which is ordered newest first
+    pub fn is_channel(&self) -> bool {
+        matches!(self, Self::Channel(_))
+    }
+}
+
+/// `<kind>-<first 8 hex digits of the arg's SHA-256>-<sanitized arg>`, the
+/// hash comes first so it survives truncation of long arguments to fit the
+/// state file name
+fn hashed_key(kind: &str, arg: &str) -> String {
+    let hash = format!("{:x}", Sha256::digest(arg.as_bytes()));
+    let key = sanitize_component(&format!("{}-{}-{}", kind, &hash[..8], arg));
+    truncate(&key, Checkpoint::MAX_SOURCE_BYTES)
+}
+
+impl FromStr for Source {
+    type Err = GiphyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || GiphyError::InvalidSource { name: s.to_owned() };
+        let (kind, arg) = match s.split_once(':') {
+            Some((kind, arg)) => (kind, Some(arg.trim()).filter(|a| !a.is_empty())),
+            None => (s, None),
+        };
+        match (kind, arg) {
+            ("channel", Some(id)) => Ok(Self::Channel(id.parse().map_err(|_| invalid())?)),
+            ("search", Some(query)) => Ok(Self::Search(query.to_owned())),
+            ("tag", Some(tag)) => Ok(Self::Tag(tag.trim_start_matches('#').to_owned())),
+            ("trending", None) => Ok(Self::Trending),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Channel(id) => write!(f, "channel:{}", id),
+            Self::Search(query) => write!(f, "search:{}", query),
+            Self::Tag(tag) => write!(f, "tag:{}", tag),
+            Self::Trending => f.write_str("trending"),
+        }
+    }
+}
+
+impl GiphyClient {
+    /// Stream every GIF of a source
+    pub fn source_gifs<'a>(&'a self, source: &Source) -> impl Stream<Item = Result<GiphyGif>> + 'a {
+        self.source_pages(source)
+            .map_ok(|page| stream::iter(page.results.into_iter().map(Ok)))
+            .try_flatten()
+    }
+
+    /// Stream the pages of a source
+    pub fn source_pages<'a>(
+        &'a self,
+        source: &Source,
+    ) -> impl Stream<Item = Result<GiphyResponse>> + 'a {
+        self.paginate(self.source_url(source), |_| true)
+    }
+
+    /// URL of the first page of a source
+    pub fn source_url(&self, source: &Source) -> Result<Url> {
+        match source {
+            Source::Channel(id) => self.feed_url(*id),
+            Source::Search(query) => {
+                let mut url = self.api_url("api/v4/search")?;
+                url.query_pairs_mut().append_pair("q", query);
+                Ok(url)
+            }
+            Source::Tag(tag) => {
+                let mut url = self.api_url("api/v4/tags")?;
+                url.path_segments_mut()
+                    .map_err(|_| GiphyError::InvalidBaseUrl {
+                        url: self.base_url().to_string(),
+                    })?
+                    .push(tag)
+                    .push("feed");
+                Ok(url)
+            }
+            Source::Trending => self.api_url("api/v4/trending"),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::manifest::Manifest;
+
+/// Number and size of archived files
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct FileTotals {
+    pub files: usize,
+    /// Sum of the recorded sizes, files linked from a content store are
+    /// counted once per link
+    pub bytes: u64,
+}
+
+/// Summary of an archive built from its manifests
+#[derive(Default, Debug)]
+pub struct ArchiveStats {
+    pub total: FileTotals,
+    /// Number of distinct GIFs, renditions of a GIF count once
+    pub gifs: usize,
+    /// Creation time of the oldest archived GIF
+    pub oldest: Option<String>,
+    /// Creation time of the newest archived GIF
+    pub newest: Option<String>,
+    /// Totals of each member directory, relative to the archive
+    pub dirs: BTreeMap<PathBuf, FileTotals>,
+}
+
+/// Summarize the files listed in the manifests anywhere below `dir`
+pub async fn archive_stats(dir: impl AsRef<Path>) -> Result<ArchiveStats> {
+    let dir = dir.as_ref();
+    let mut stats = ArchiveStats::default();
+    let mut gifs = HashSet::new();
+
+    for manifest in Manifest::load_all(dir).await? {
+        let relative = manifest.dir().strip_prefix(dir).unwrap_or(manifest.dir());
+        let totals = stats.dirs.entry(relative.to_owned()).or_default();
+        for entry in manifest.entries() {
+            let bytes = entry.size.unwrap_or_default();
+            totals.files += 1;
+            totals.bytes += bytes;
+            stats.total.files += 1;
+            stats.total.bytes += bytes;
+            gifs.insert(entry.id.clone());
+
+            let time = &entry.create_time;
+            if stats.oldest.as_ref().is_none_or(|t| time < t) {
+                stats.oldest = Some(time.clone());
+            }
+            if stats.newest.as_ref().is_none_or(|t| time > t) {
+                stats.newest = Some(time.clone());
+            }
+        }
+    }
+
+    stats.gifs = gifs.len();
+    Ok(stats)
+}
@@ -0,0 +1,132 @@
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Result};
+use tokio::fs;
+
+use crate::digest::FileDigest;
+use crate::error::GiphyError;
+use crate::partial::part_path;
+
+/// How archived files refer to their content in a [`ContentStore`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StoreLink {
+    /// Hard link to the stored file, the store must be on the same filesystem
+    Hardlink,
+    /// Symbolic link to the absolute path of the stored file
+    Symlink,
+}
+
+impl FromStr for StoreLink {
+    type Err = GiphyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hardlink" => Ok(Self::Hardlink),
+            "symlink" => Ok(Self::Symlink),
+            _ => Err(GiphyError::InvalidLinkType { name: s.to_owned() }),
+        }
+    }
+}
+
+/// Directory holding each downloaded file once, named by its SHA-256
+///
+/// Files are stored as `<dir>/<first two hex digits>/<sha256>` and the paths
+/// in member directories link to them, so media reposted by several channels
+/// or identical renditions only take up space once. With symbolic links the
+/// store may be on another filesystem than the archive.
+#[derive(Clone, Debug)]
+pub struct ContentStore {
+    pub dir: PathBuf,
+    pub link: StoreLink,
+}
+
+impl ContentStore {
+    pub fn new(dir: impl Into<PathBuf>, link: StoreLink) -> Self {
+        Self {
+            dir: dir.into(),
+            link,
+        }
+    }
+
+    /// Path of the stored file with digest `sha256`
+    pub fn object_path(&self, sha256: &str) -> PathBuf {
+        self.dir.join(&sha256[..2.min(sha256.len())]).join(sha256)
+    }
+
+    /// Move a downloaded file into the store unless its content is already
+    /// there, returns the path of the stored file
+    ///
+    /// A stored file of the wrong size was damaged after it was stored. It is
+    /// not replaced since other paths may still link to it, and the error
+    /// asks for it to be repaired instead.
+    pub(crate) async fn insert(&self, file: &Path, digest: &FileDigest) -> Result<PathBuf> {
+        let object = self.object_path(&digest.sha256);
+        if let Ok(metadata) = fs::metadata(&object).await {
+            fs::remove_file(file).await?;
+            if metadata.len() != digest.size {
+                bail!(GiphyError::DamagedObject {
+                    path: object.to_string_lossy().into_owned(),
+                    expected: digest.size,
+                    actual: metadata.len(),
+                });
+            }
+            return Ok(object);
+        }
+        if let Some(dir) = object.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        match fs::rename(file, &object).await {
+            Err(e) if e.kind() == ErrorKind::CrossesDevices => move_file(file, &object).await?,
+            result => result?,
+        }
+        Ok(object)
+    }
+
+    /// Create `path` as a link to the stored file `object`, replacing
+    /// whatever is at `path` only once the link exists
+    pub(crate) async fn link(&self, object: &Path, path: &Path) -> Result<()> {
+        let part = part_path(path);
+        match fs::symlink_metadata(&part).await {
+            Ok(_) => fs::remove_file(&part).await?,
+            Err(e) if e.kind() == ErrorKind::NotFound => (),
+            Err(e) => return Err(e.into()),
+        }
+
+        let result = match self.link {
+            StoreLink::Hardlink => fs::hard_link(object, &part).await,
+            StoreLink::Symlink => symlink(&std::path::absolute(object)?, &part).await,
+        };
+        result.with_context(|| {
+            format!(
+                "Failed to link {} to {}",
+                path.to_string_lossy(),
+                object.to_string_lossy()
+            )
+        })?;
+        fs::rename(&part, path).await?;
+        Ok(())
+    }
+}
+
+/// Move a file to another filesystem, it is copied next to `to` and only
+/// renamed into place once written to disk
+async fn move_file(from: &Path, to: &Path) -> Result<()> {
+    let part = part_path(to);
+    fs::copy(from, &part).await?;
+    fs::File::open(&part).await?.sync_all().await?;
+    fs::rename(&part, to).await?;
+    fs::remove_file(from).await?;
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn symlink(target: &Path, path: &Path) -> std::io::Result<()> {
+    fs::symlink(target, path).await
+}
+
+#[cfg(windows)]
+async fn symlink(target: &Path, path: &Path) -> std::io::Result<()> {
+    fs::symlink_file(target, path).await
+}
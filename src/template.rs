@@ -0,0 +1,202 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::GiphyError;
+use crate::rendition::RenditionSpec;
+use crate::sanitize::sanitize_field;
+use crate::types::GiphyGif;
+
+/// A file or directory name with `{placeholder}` fields filled in per GIF
+///
+/// Fields are `id`, `index_id`, `username`, `user_id`, `title`, `date`
+/// (`YYYYMMDD`), `year`, `month`, `day`, `rendition` and `ext`. GIFs without
+/// an uploader have the username [`Template::ANONYMOUS`] and user ID 0, GIFs
+/// outside of channel feeds have index ID 0. A width such
+/// as `{index_id:012}` pads the value with zeros, `{{` and `}}` are literal
+/// braces. `/` in the template creates subdirectories, path separators in
+/// field values are replaced.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Template {
+    source: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Part {
+    Literal(String),
+    Field { field: Field, width: usize },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Field {
+    Id,
+    IndexId,
+    Username,
+    UserId,
+    Title,
+    Date,
+    Year,
+    Month,
+    Day,
+    Rendition,
+    Ext,
+}
+
+impl FromStr for Field {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "id" => Self::Id,
+            "index_id" => Self::IndexId,
+            "username" => Self::Username,
+            "user_id" => Self::UserId,
+            "title" => Self::Title,
+            "date" => Self::Date,
+            "year" => Self::Year,
+            "month" => Self::Month,
+            "day" => Self::Day,
+            "rendition" => Self::Rendition,
+            "ext" => Self::Ext,
+            _ => return Err(()),
+        })
+    }
+}
+
+impl Template {
+    /// Default file name, `20220314_username_000123456789_id.mp4`
+    pub const DEFAULT_FILENAME: &'static str = "{date}_{username}_{index_id:012}_{id}.{ext}";
+    /// Default directory below the download directory
+    pub const DEFAULT_DIR: &'static str = "{username}";
+    /// Username of GIFs without an uploader
+    pub const ANONYMOUS: &'static str = "anonymous";
+
+    /// Template for [`Template::DEFAULT_FILENAME`]
+    pub fn default_filename() -> Self {
+        Self::DEFAULT_FILENAME.parse().unwrap()
+    }
+
+    /// Template for [`Template::DEFAULT_DIR`]
+    pub fn default_dir() -> Self {
+        Self::DEFAULT_DIR.parse().unwrap()
+    }
+
+    /// Fill in the fields for a rendition of `gif` saved with extension `ext`
+    pub fn render(
+        &self,
+        gif: &GiphyGif,
+        rendition: &RenditionSpec,
+        ext: &str,
+    ) -> Result<String, GiphyError> {
+        let date = gif
+            .create_time
+            .split_once('T')
+            .map(|(date, _)| date.split('-').collect::<Vec<_>>())
+            .filter(|parts| parts.len() == 3)
+            .ok_or_else(|| GiphyError::InvalidTime {
+                date: gif.create_time.clone(),
+            })?;
+
+        let mut out = String::new();
+        for part in &self.parts {
+            let (field, width) = match part {
+                Part::Literal(s) => {
+                    out.push_str(s);
+                    continue;
+                }
+                Part::Field { field, width } => (field, *width),
+            };
+            let value = match field {
+                Field::Id => sanitize_field(&gif.id),
+                Field::IndexId => gif.index_id.unwrap_or_default().to_string(),
+                Field::Username => match &gif.user {
+                    Some(user) => sanitize_field(&user.username),
+                    None => Self::ANONYMOUS.to_owned(),
+                },
+                Field::UserId => gif.user.as_ref().map_or(0, |u| u.id).to_string(),
+                Field::Title => sanitize_field(&gif.title),
+                Field::Date => date.concat(),
+                Field::Year => date[0].to_owned(),
+                Field::Month => date[1].to_owned(),
+                Field::Day => date[2].to_owned(),
+                Field::Rendition => rendition.label(),
+                Field::Ext => sanitize_field(ext),
+            };
+            out.push_str(&format!("{:0>width$}", value, width = width));
+        }
+        Ok(out)
+    }
+}
+
+impl FromStr for Template {
+    type Err = GiphyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| GiphyError::InvalidTemplate {
+            template: s.to_owned(),
+            reason: reason.to_owned(),
+        };
+
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '}' => return Err(invalid("unmatched `}`")),
+                '{' => {
+                    let mut spec = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => spec.push(c),
+                            None => return Err(invalid("unclosed `{`")),
+                        }
+                    }
+                    let (name, width) = match spec.split_once(':') {
+                        Some((name, width)) => (name, Some(width)),
+                        None => (spec.as_str(), None),
+                    };
+                    let field = name
+                        .parse()
+                        .map_err(|_| invalid(&format!("unknown field `{}`", name)))?;
+                    let width = match width {
+                        Some(w) => w
+                            .parse()
+                            .map_err(|_| invalid(&format!("invalid width `{}`", w)))?,
+                        None => 0,
+                    };
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field { field, width });
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        if parts.is_empty() {
+            return Err(invalid("empty template"));
+        }
+        Ok(Self {
+            source: s.to_owned(),
+            parts,
+        })
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::BTreeMap;
+
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+use crate::images::Images;
+
+/// A single page of a Giphy feed
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GiphyResponse {
+    pub next: Option<String>,
+    #[serde(deserialize_with = "deserialize_gifs")]
+    pub results: Vec<GiphyGif>,
+}
+
+/// A GIF in a Giphy feed, fields that aren't modeled are kept in `other`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GiphyGif {
+    pub id: String,
+    /// Position in the uploader's channel, only sent in channel feeds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_id: Option<u64>,
+    pub images: Images,
+    pub title: String,
+    /// Uploader of the GIF, `None` for anonymous uploads
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<GiphyUser>,
+    #[serde(rename = "create_datetime")]
+    pub create_time: String,
+    #[serde(flatten)]
+    pub other: BTreeMap<String, serde_json::Value>,
+    /// The feed entry exactly as Giphy sent it, set by [`GiphyGif::from_value`]
+    #[serde(skip)]
+    pub raw: serde_json::Value,
+}
+
+impl GiphyGif {
+    /// Parse a feed entry, keeping the original JSON in `raw`
+    pub fn from_value(raw: serde_json::Value) -> serde_json::Result<Self> {
+        let mut gif = Self::deserialize(&raw)?;
+        gif.raw = raw;
+        Ok(gif)
+    }
+}
+
+/// Parse feed entries with [`GiphyGif::from_value`]
+fn deserialize_gifs<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<GiphyGif>, D::Error> {
+    Vec::<serde_json::Value>::deserialize(d)?
+        .into_iter()
+        .map(|raw| GiphyGif::from_value(raw).map_err(de::Error::custom))
+        .collect()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GiphyUser {
+    pub id: u64,
+    pub name: String,
+    pub username: String,
+    #[serde(flatten)]
+    pub other: BTreeMap<String, serde_json::Value>,
+}
+
+/// A channel, the collection of GIFs a member uploaded
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GiphyChannel {
+    /// Member ID of the channel's feed
+    pub id: u64,
+    pub slug: String,
+    pub user: GiphyUser,
+    #[serde(flatten)]
+    pub other: BTreeMap<String, serde_json::Value>,
+}
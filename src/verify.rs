@@ -0,0 +1,110 @@
+use std::fmt;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use tokio::fs;
+
+use crate::digest::digest_file;
+use crate::manifest::{Manifest, ManifestEntry};
+
+/// Problem with an archived file found by [`verify_archive`]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum FileProblem {
+    /// The file listed in the manifest doesn't exist
+    Missing,
+    /// The file is shorter than when it was archived
+    Truncated { expected: u64, actual: u64 },
+    /// The file has a different size or SHA-256 than when it was archived
+    Modified,
+}
+
+impl fmt::Display for FileProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Missing => f.write_str("missing"),
+            Self::Truncated { expected, actual } => {
+                write!(f, "truncated, {} of {} bytes", actual, expected)
+            }
+            Self::Modified => f.write_str("modified"),
+        }
+    }
+}
+
+/// An archived file that failed verification
+#[derive(Clone, Debug)]
+pub struct FileIssue {
+    pub path: PathBuf,
+    pub entry: ManifestEntry,
+    pub problem: FileProblem,
+}
+
+/// Result of checking an archive against its manifests
+#[derive(Default, Debug)]
+pub struct VerifyReport {
+    /// Number of files listed in the manifests
+    pub checked: usize,
+    /// Files that were only checked by size since no SHA-256 was recorded
+    pub unhashed: usize,
+    pub issues: Vec<FileIssue>,
+}
+
+impl VerifyReport {
+    /// Whether every file matches its manifest entry
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Check every file listed in the manifests anywhere below `dir` against
+/// its recorded size and SHA-256
+pub async fn verify_archive(dir: impl AsRef<Path>) -> Result<VerifyReport> {
+    let mut report = VerifyReport::default();
+    for manifest in Manifest::load_all(dir).await? {
+        for entry in manifest.entries() {
+            let path = manifest.dir().join(&entry.filename);
+            report.checked += 1;
+            if entry.sha256.is_none() {
+                report.unhashed += 1;
+            }
+            if let Some(problem) = check_file(&path, entry).await? {
+                report.issues.push(FileIssue {
+                    path,
+                    entry: entry.clone(),
+                    problem,
+                });
+            }
+        }
+    }
+
+    report.issues.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(report)
+}
+
+/// Compare a file with its manifest entry
+async fn check_file(path: &Path, entry: &ManifestEntry) -> Result<Option<FileProblem>> {
+    let len = match fs::metadata(path).await {
+        Ok(metadata) => metadata.len(),
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Some(FileProblem::Missing)),
+        Err(e) => return Err(e.into()),
+    };
+    match entry.size {
+        Some(expected) if len < expected => {
+            return Ok(Some(FileProblem::Truncated {
+                expected,
+                actual: len,
+            }))
+        }
+        Some(expected) if len != expected => return Ok(Some(FileProblem::Modified)),
+        _ => (),
+    }
+
+    let sha256 = match &entry.sha256 {
+        Some(s) => s,
+        None => return Ok(None),
+    };
+    match &digest_file(path).await?.sha256 == sha256 {
+        true => Ok(None),
+        false => Ok(Some(FileProblem::Modified)),
+    }
+}
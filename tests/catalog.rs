@@ -0,0 +1,119 @@
+mod common;
+
+use chrono::{Duration, Utc};
+use common::{giphy_mock, media_body, MockResponse};
+use futures::TryStreamExt;
+use giphy_download::{Catalog, GiphyClient, RetryPolicy};
+
+#[tokio::test]
+async fn gifs_are_recorded_as_pages_are_fetched() {
+    let server = giphy_mock("").await;
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .catalog(Catalog::open_in_memory().unwrap())
+        .build()
+        .unwrap();
+
+    client.gifs(1234).try_collect::<Vec<_>>().await.unwrap();
+
+    let catalog = client.catalog().unwrap();
+    let ids: Vec<_> = catalog
+        .member_gifs(1234, None)
+        .unwrap()
+        .into_iter()
+        .map(|g| g.id)
+        .collect();
+    assert_eq!(ids, ["aBcDeF123", "GhIjKl456", "MnOpQr789"]);
+    let gifs_2021 = catalog.member_gifs(1234, Some(2021)).unwrap();
+    assert_eq!(gifs_2021.len(), 1);
+    assert_eq!(gifs_2021[0].id, "MnOpQr789");
+    let username: String = catalog
+        .connection()
+        .query_row("SELECT username FROM members WHERE id = 1234", [], |row| {
+            row.get(0)
+        })
+        .unwrap();
+    assert_eq!(username, "mockchannel");
+    let data: String = catalog
+        .connection()
+        .query_row("SELECT data FROM gifs WHERE id = 'aBcDeF123'", [], |row| {
+            row.get(0)
+        })
+        .unwrap();
+    let data: serde_json::Value = serde_json::from_str(&data).unwrap();
+    assert_eq!(data["images"]["original"]["frames"], "42");
+}
+
+#[tokio::test]
+async fn downloads_are_recorded_with_their_paths() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("catalog.db");
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .catalog(Catalog::open(&path).unwrap())
+        .build()
+        .unwrap();
+
+    client
+        .archive(1234, dir.path(), &Default::default())
+        .await
+        .unwrap();
+    drop(client);
+
+    let catalog = Catalog::open(&path).unwrap();
+    let files = catalog.files("aBcDeF123").unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].rendition, "source");
+    assert_eq!(
+        files[0].size,
+        Some(media_body("/media/aBcDeF123/source.mp4").len() as u64)
+    );
+    assert!(files[0].sha256.is_some());
+    assert_eq!(
+        files[0].path,
+        dir.path()
+            .join("mockchannel")
+            .join("20220314_mockchannel_000310000002_aBcDeF123.mp4")
+            .to_string_lossy()
+    );
+    let attempts: u32 = catalog
+        .connection()
+        .query_row("SELECT count(*) FROM attempts", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(attempts, 3);
+}
+
+#[tokio::test]
+async fn failed_downloads_are_recorded() {
+    let server = giphy_mock("").await;
+    let media = "/media/GhIjKl456/source.gif";
+    server.reset_route(media);
+    server.route(media, MockResponse::status(404));
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .retry(RetryPolicy::none())
+        .catalog(Catalog::open_in_memory().unwrap())
+        .build()
+        .unwrap();
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    let catalog = client.catalog().unwrap();
+    let failures = catalog
+        .failures_since(Utc::now() - Duration::days(7))
+        .unwrap();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].gif_id, "GhIjKl456");
+    assert_eq!(failures[0].rendition.as_deref(), Some("source"));
+    assert!(failures[0].error.contains("404"));
+    assert!(catalog.files("GhIjKl456").unwrap().is_empty());
+    assert!(catalog
+        .failures_since(Utc::now() + Duration::days(1))
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::{giphy_mock, media_files, MockResponse};
+use giphy_download::{ArchiveOptions, Checkpoint, GifStatus, GiphyClient, RetryPolicy};
+
+const FIRST_PAGE: &str = "/api/v4/channels/1234/feed";
+const NEXT_PAGE: &str = "/api/v4/channels/1234/feed/?offset=2";
+
+fn resume() -> ArchiveOptions {
+    ArchiveOptions {
+        resume: true,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn checkpoint_records_cursor_of_unfinished_page() {
+    let server = giphy_mock("").await;
+    server.route_once(NEXT_PAGE, MockResponse::status(500));
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .retry(RetryPolicy::none())
+        .build()
+        .unwrap();
+
+    assert!(client
+        .archive(1234, dir.path(), &ArchiveOptions::default())
+        .await
+        .is_err());
+
+    let checkpoint = Checkpoint::load(dir.path(), 1234).await.unwrap().unwrap();
+    assert_eq!(
+        checkpoint.cursor.as_deref(),
+        Some("https://giphy.com/api/v4/channels/1234/feed/?offset=2")
+    );
+    assert_eq!(checkpoint.gifs.len(), 2);
+    assert!(checkpoint.is_done("aBcDeF123"));
+    assert!(checkpoint.is_done("GhIjKl456"));
+}
+
+#[tokio::test]
+async fn resume_continues_from_cursor() {
+    let server = giphy_mock("").await;
+    server.route_once(NEXT_PAGE, MockResponse::status(500));
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .retry(RetryPolicy::none())
+        .build()
+        .unwrap();
+
+    assert!(client
+        .archive(1234, dir.path(), &ArchiveOptions::default())
+        .await
+        .is_err());
+    client.archive(1234, dir.path(), &resume()).await.unwrap();
+
+    assert_eq!(server.request_count(FIRST_PAGE), 1);
+    assert_eq!(server.request_count(NEXT_PAGE), 2);
+    let checkpoint = Checkpoint::load(dir.path(), 1234).await.unwrap().unwrap();
+    assert_eq!(checkpoint.cursor, None);
+    assert_eq!(checkpoint.gifs.get("MnOpQr789"), Some(&GifStatus::Done));
+    assert_eq!(media_files(dir.path().join("mockchannel")).len(), 3);
+}
+
+#[tokio::test]
+async fn resume_skips_completed_gifs() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let mut checkpoint = Checkpoint::new(1234);
+    checkpoint
+        .gifs
+        .insert("aBcDeF123".to_owned(), GifStatus::Done);
+    checkpoint.save(dir.path()).await.unwrap();
+    client.archive(1234, dir.path(), &resume()).await.unwrap();
+
+    assert_eq!(server.request_count("/media/aBcDeF123/source.mp4"), 0);
+    assert_eq!(server.request_count("/media/GhIjKl456/source.gif"), 1);
+}
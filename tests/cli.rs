@@ -0,0 +1,124 @@
+mod common;
+
+use std::process::{Command, Output};
+
+use common::{giphy_mock, media_files};
+
+/// Run the CLI against a mock server
+async fn run(url: String, args: Vec<String>) -> Output {
+    tokio::task::spawn_blocking(move || {
+        Command::new(env!("CARGO_BIN_EXE_giphy-download"))
+            .args(args)
+            .env("GIPHY_BASE_URL", url)
+            .output()
+            .unwrap()
+    })
+    .await
+    .unwrap()
+}
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|a| a.to_string()).collect()
+}
+
+#[tokio::test]
+async fn list_prints_feed_without_downloading() {
+    let server = giphy_mock("").await;
+
+    let output = run(server.url(), args(&["list", "-m", "1234", "--limit", "2"])).await;
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let ids: Vec<_> = stdout
+        .lines()
+        .map(|l| l.split('\t').next().unwrap())
+        .collect();
+    assert_eq!(ids, ["aBcDeF123", "GhIjKl456"]);
+    assert!(stdout.starts_with("aBcDeF123\t310000002\t2022-03-14T09:26:53+0000\tmockchannel\t"));
+    assert!(server.requests().iter().all(|r| !r.starts_with("/media/")));
+}
+
+#[tokio::test]
+async fn list_json_prints_feed_entries_as_sent() {
+    let server = giphy_mock("").await;
+
+    let output = run(
+        server.url(),
+        args(&["list", "-m", "1234", "--limit", "1", "--json"]),
+    )
+    .await;
+
+    assert!(output.status.success());
+    let entry: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(entry["id"], "aBcDeF123");
+    assert_eq!(entry["images"]["original"]["frames"], "42");
+}
+
+#[tokio::test]
+async fn stats_summarize_synced_archive() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let directory = dir.path().to_string_lossy().into_owned();
+
+    let sync = run(
+        server.url(),
+        args(&["--directory", &directory, "sync", "-m", "1234"]),
+    )
+    .await;
+    let stats = run(server.url(), args(&["stats", "-d", &directory])).await;
+
+    assert!(sync.status.success());
+    assert!(stats.status.success());
+    assert_eq!(
+        String::from_utf8(stats.stdout).unwrap(),
+        "3 files of 3 GIFs, 114 bytes\n\
+         Created 2021-12-31T23:59:59+0000 to 2022-03-14T09:26:53+0000\n  \
+         mockchannel: 3 files, 114 bytes\n"
+    );
+}
+
+#[tokio::test]
+async fn quiet_sync_only_prints_summary() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let directory = dir.path().to_string_lossy().into_owned();
+
+    let output = run(
+        server.url(),
+        args(&["sync", "-q", "-m", "mockchannel", "-d", &directory]),
+    )
+    .await;
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Summary:\n  channel:1234: 3 archived, 0 failed\n"
+    );
+    assert_eq!(media_files(dir.path().join("mockchannel")).len(), 3);
+}
+
+#[tokio::test]
+async fn incremental_takes_at_most_one_value() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let directory = dir.path().to_string_lossy().into_owned();
+
+    let output = run(
+        server.url(),
+        args(&[
+            "sync",
+            "-m",
+            "1234",
+            "-d",
+            &directory,
+            "--incremental",
+            "5",
+            "6",
+        ]),
+    )
+    .await;
+
+    assert!(!output.status.success());
+    assert!(server.requests().is_empty());
+}
@@ -0,0 +1,344 @@
+//! Mock Giphy server replaying recorded feed pages and serving fake media
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use hyper::header::{CONTENT_LENGTH, IF_RANGE, RANGE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::HeaderMap;
+use hyper::{Body, Request, Response, Server};
+
+/// Host used for media URLs in the recorded fixtures
+const MEDIA_HOST: &str = "https://media.giphy.com";
+
+/// Recorded pages of the channel feed for member 1234
+const CHANNEL_1234: &[(&str, &str)] = &[
+    (
+        "/api/v4/channels/1234/feed",
+        include_str!("../fixtures/channel_1234_page1.json"),
+    ),
+    (
+        "/api/v4/channels/1234/feed/?offset=2",
+        include_str!("../fixtures/channel_1234_page2.json"),
+    ),
+];
+
+/// Recorded search, tag and trending feeds, their GIFs have no `index_id`
+/// and most have no `user`
+const FEEDS: &[(&str, &str)] = &[
+    (
+        "/api/v4/search?q=hello",
+        include_str!("../fixtures/search_hello.json"),
+    ),
+    (
+        "/api/v4/tags/waving/feed",
+        include_str!("../fixtures/tag_waving.json"),
+    ),
+    (
+        "/api/v4/trending",
+        include_str!("../fixtures/trending.json"),
+    ),
+];
+
+/// Recorded channel search for the username of member 1234
+const CHANNEL_SEARCH: (&str, &str) = (
+    "/api/v4/channels/search?q=mockchannel",
+    include_str!("../fixtures/channel_search_mockchannel.json"),
+);
+
+#[derive(Clone, Debug)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// Abort the connection after sending this many body bytes
+    pub truncate: Option<usize>,
+    /// Wait this long before responding
+    pub delay: Option<Duration>,
+}
+
+impl MockResponse {
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: 200,
+            headers: Vec::new(),
+            body: body.into(),
+            truncate: None,
+            delay: None,
+        }
+    }
+
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+            truncate: None,
+            delay: None,
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    pub fn truncate(mut self, len: usize) -> Self {
+        self.truncate = Some(len);
+        self
+    }
+
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    fn header_value(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Serve `Range: bytes=N-` requests if the response advertises
+    /// `Accept-Ranges` and `If-Range` matches its validators
+    fn for_request(mut self, headers: &HeaderMap) -> Self {
+        let start = headers
+            .get(RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("bytes="))
+            .and_then(|v| v.strip_suffix('-'))
+            .and_then(|v| v.parse::<usize>().ok());
+        let start = match start {
+            Some(s)
+                if self.status == 200 && self.header_value("accept-ranges") == Some("bytes") =>
+            {
+                s
+            }
+            _ => return self,
+        };
+        if let Some(if_range) = headers.get(IF_RANGE).and_then(|v| v.to_str().ok()) {
+            if self.header_value("etag") != Some(if_range)
+                && self.header_value("last-modified") != Some(if_range)
+            {
+                return self;
+            }
+        }
+        if start >= self.body.len() {
+            return MockResponse::status(416);
+        }
+
+        let total = self.body.len();
+        self.status = 206;
+        self.body.drain(..start);
+        self.truncate = self.truncate.map(|t| t.saturating_sub(start));
+        self.header(
+            "Content-Range",
+            &format!("bytes {}-{}/{}", start, total - 1, total),
+        )
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// Queued responses per path and query, the last one is repeated
+    routes: HashMap<String, Vec<MockResponse>>,
+    /// Path and query and headers of every received request
+    requests: Vec<(String, HeaderMap)>,
+    /// Requests currently being handled and the most seen at once
+    in_flight: usize,
+    max_in_flight: usize,
+}
+
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockServer {
+    pub async fn start() -> Self {
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let service_state = state.clone();
+        let make_svc = make_service_fn(move |_| {
+            let state = service_state.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |req| handle(state.clone(), req))) }
+        });
+        let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_svc);
+        let addr = server.local_addr();
+        tokio::spawn(server);
+
+        Self { addr, state }
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Queue a response for a path and query
+    pub fn route(&self, path: &str, response: MockResponse) {
+        self.state
+            .lock()
+            .unwrap()
+            .routes
+            .entry(path.to_owned())
+            .or_default()
+            .push(response);
+    }
+
+    /// Serve a response once before the queued ones
+    pub fn route_once(&self, path: &str, response: MockResponse) {
+        self.state
+            .lock()
+            .unwrap()
+            .routes
+            .entry(path.to_owned())
+            .or_default()
+            .insert(0, response);
+    }
+
+    /// Remove all queued responses for a path and query
+    pub fn reset_route(&self, path: &str) {
+        self.state.lock().unwrap().routes.remove(path);
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.state
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// Headers of every request received for a path and query
+    pub fn request_headers(&self, path: &str) -> Vec<HeaderMap> {
+        self.state
+            .lock()
+            .unwrap()
+            .requests
+            .iter()
+            .filter(|(p, _)| p == path)
+            .map(|(_, headers)| headers.clone())
+            .collect()
+    }
+
+    pub fn request_count(&self, path: &str) -> usize {
+        self.requests().iter().filter(|r| *r == path).count()
+    }
+
+    /// Most requests handled at the same time
+    pub fn max_in_flight(&self) -> usize {
+        self.state.lock().unwrap().max_in_flight
+    }
+}
+
+async fn handle(
+    state: Arc<Mutex<State>>,
+    req: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let key = req
+        .uri()
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or("/")
+        .to_owned();
+
+    let mock = {
+        let mut state = state.lock().unwrap();
+        state.requests.push((key.clone(), req.headers().clone()));
+        state.in_flight += 1;
+        state.max_in_flight = state.max_in_flight.max(state.in_flight);
+        match state.routes.get_mut(&key) {
+            Some(queue) if queue.len() > 1 => queue.remove(0),
+            Some(queue) => queue[0].clone(),
+            None => MockResponse::status(404),
+        }
+    };
+    if let Some(delay) = mock.delay {
+        tokio::time::sleep(delay).await;
+    }
+    state.lock().unwrap().in_flight -= 1;
+    let mock = mock.for_request(req.headers());
+
+    let mut resp = Response::builder()
+        .status(mock.status)
+        .header(CONTENT_LENGTH, mock.body.len());
+    for (name, value) in &mock.headers {
+        resp = resp.header(name, value);
+    }
+    let body = match mock.truncate {
+        Some(len) => {
+            let (mut sender, body) = Body::channel();
+            let head = mock.body[..len].to_vec();
+            tokio::spawn(async move {
+                let _ = sender.send_data(head.into()).await;
+                // Give the data time to reach the client before aborting
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                sender.abort();
+            });
+            body
+        }
+        None => Body::from(mock.body),
+    };
+    Ok(resp.body(body).unwrap())
+}
+
+/// Names of the media files directly in `dir`
+pub fn media_files(dir: impl AsRef<Path>) -> Vec<String> {
+    let mut names: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap())
+        .filter(|e| e.file_type().unwrap().is_file())
+        .map(|e| e.file_name().into_string().unwrap())
+        .filter(|n| n != "manifest.jsonl" && !n.ends_with(".json"))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Fake media body served for a media path
+pub fn media_body(path: &str) -> Vec<u8> {
+    format!("fake media {}", path).into_bytes()
+}
+
+/// Start a mock Giphy server serving the recorded channel, feeds and their
+/// GIFs under `prefix`
+pub async fn giphy_mock(prefix: &str) -> MockServer {
+    let server = MockServer::start().await;
+    for (path, page) in CHANNEL_1234.iter().chain(FEEDS) {
+        for media in media_paths(&serde_json::from_str(page).unwrap()) {
+            server.route(&media, MockResponse::ok(media_body(&media)));
+        }
+        let page = page.replace(MEDIA_HOST, &server.url());
+        let value: serde_json::Value = serde_json::from_str(&page).unwrap();
+        for gif in value["results"].as_array().unwrap() {
+            let path = format!("{}/api/v4/gifs/{}", prefix, gif["id"].as_str().unwrap());
+            server.route(&path, MockResponse::ok(gif.to_string()));
+        }
+        server.route(&format!("{}{}", prefix, path), MockResponse::ok(page));
+    }
+    let (path, search) = CHANNEL_SEARCH;
+    server.route(&format!("{}{}", prefix, path), MockResponse::ok(search));
+    server
+}
+
+/// Paths of all media URLs referenced in a recorded feed page
+fn media_paths(value: &serde_json::Value) -> Vec<String> {
+    match value {
+        serde_json::Value::String(s) => s
+            .strip_prefix(MEDIA_HOST)
+            .map(|p| vec![p.to_owned()])
+            .unwrap_or_default(),
+        serde_json::Value::Array(a) => a.iter().flat_map(media_paths).collect(),
+        serde_json::Value::Object(o) => o.values().flat_map(media_paths).collect(),
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{giphy_mock, media_body, MockResponse, MockServer};
+use futures::StreamExt;
+use giphy_download::{GiphyClient, GiphyClientBuilder};
+
+const NEXT_PAGE: &str = "/api/v4/channels/1234/feed/?offset=2";
+
+/// Make every media download of the recorded channel take a while
+fn slow_media(server: &MockServer) {
+    for path in [
+        "/media/aBcDeF123/source.mp4",
+        "/media/GhIjKl456/source.gif",
+        "/media/MnOpQr789/source.mov",
+    ] {
+        server.reset_route(path);
+        server.route(
+            path,
+            MockResponse::ok(media_body(path)).delay(Duration::from_millis(100)),
+        );
+    }
+}
+
+async fn max_downloads_in_flight(builder: GiphyClientBuilder) -> usize {
+    let server = giphy_mock("").await;
+    slow_media(&server);
+    let dir = tempfile::tempdir().unwrap();
+    let client = builder.base_url(server.url()).build().unwrap();
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+    server.max_in_flight()
+}
+
+#[tokio::test]
+async fn jobs_limit_concurrent_downloads() {
+    assert!(max_downloads_in_flight(GiphyClient::builder()).await > 1);
+    assert_eq!(
+        max_downloads_in_flight(GiphyClient::builder().concurrency(1)).await,
+        1
+    );
+}
+
+#[tokio::test]
+async fn connections_per_host_limit_concurrent_requests() {
+    let builder = GiphyClient::builder()
+        .concurrency(10)
+        .connections_per_host(1);
+    assert_eq!(max_downloads_in_flight(builder).await, 1);
+}
+
+#[tokio::test]
+async fn next_page_is_prefetched_when_enabled() {
+    for prefetch in [false, true] {
+        let server = giphy_mock("").await;
+        let client = GiphyClient::builder()
+            .base_url(server.url())
+            .prefetch_pages(prefetch)
+            .build()
+            .unwrap();
+
+        let pages = client.pages(1234);
+        futures::pin_mut!(pages);
+        pages.next().await.unwrap().unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(server.request_count(NEXT_PAGE), prefetch as usize);
+
+        let page = pages.next().await.unwrap().unwrap();
+        assert_eq!(page.results[0].id, "MnOpQr789");
+        assert!(pages.next().await.is_none());
+        assert_eq!(server.request_count(NEXT_PAGE), 1);
+    }
+}
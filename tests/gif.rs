@@ -0,0 +1,99 @@
+mod common;
+
+use std::process::Command;
+
+use common::{giphy_mock, media_body, media_files};
+use giphy_download::{ArchiveSummary, GifId, GiphyClient, RetryPolicy};
+
+#[test]
+fn gif_ids_parse_from_urls() {
+    let parse = |s: &str| s.parse::<GifId>().unwrap().0;
+    assert_eq!(parse("aBcDeF123"), "aBcDeF123");
+    assert_eq!(
+        parse("https://giphy.com/gifs/mockchannel-waving-hello-aBcDeF123"),
+        "aBcDeF123"
+    );
+    assert_eq!(parse("giphy.com/gifs/aBcDeF123"), "aBcDeF123");
+    assert_eq!(parse("https://giphy.com/embed/aBcDeF123"), "aBcDeF123");
+    assert_eq!(
+        parse("https://media.giphy.com/media/aBcDeF123/giphy.gif"),
+        "aBcDeF123"
+    );
+    assert_eq!(
+        parse("https://media4.giphy.com/media/v1.Y2lkPTc5MGI3NjEx/aBcDeF123/giphy.gif"),
+        "aBcDeF123"
+    );
+    assert_eq!(parse("https://i.giphy.com/aBcDeF123.gif"), "aBcDeF123");
+    assert_eq!(parse("i.giphy.com/aBcDeF123.webp"), "aBcDeF123");
+    assert!("https://giphy.com/channel/mockchannel"
+        .parse::<GifId>()
+        .is_err());
+    assert!("https://example.com/gifs/aBcDeF123"
+        .parse::<GifId>()
+        .is_err());
+    assert!("../aBcDeF123".parse::<GifId>().is_err());
+}
+
+#[tokio::test]
+async fn single_gifs_use_the_archive_layout() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .retry(RetryPolicy::none())
+        .build()
+        .unwrap();
+
+    let ids = ["aBcDeF123", "missing"].map(|id| id.parse().unwrap());
+    let summary = client.download_gifs(&ids, dir.path()).await;
+
+    assert_eq!(
+        summary,
+        ArchiveSummary {
+            archived: 1,
+            failed: 1
+        }
+    );
+    let path = dir
+        .path()
+        .join("mockchannel")
+        .join("20220314_mockchannel_000310000002_aBcDeF123.mp4");
+    assert_eq!(
+        std::fs::read(path).unwrap(),
+        media_body("/media/aBcDeF123/source.mp4")
+    );
+    assert_eq!(server.request_count("/api/v4/channels/1234/feed"), 0);
+}
+
+#[tokio::test]
+async fn cli_downloads_gif_urls_without_members() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = tokio::task::spawn_blocking({
+        let url = server.url();
+        let dir = dir.path().to_owned();
+        move || {
+            Command::new(env!("CARGO_BIN_EXE_giphy-download"))
+                .args(["get", "https://giphy.com/gifs/thumbs-up-GhIjKl456"])
+                .args(["MnOpQr789", "--directory"])
+                .arg(dir)
+                .env("GIPHY_BASE_URL", url)
+                .output()
+                .unwrap()
+        }
+    })
+    .await
+    .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        media_files(dir.path().join("mockchannel")),
+        [
+            "20211231_mockchannel_000300000000_MnOpQr789.mov",
+            "20220301_mockchannel_000310000001_GhIjKl456.gif"
+        ]
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.ends_with("Summary:\n  GIFs: 2 archived, 0 failed\n"));
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use common::{giphy_mock, media_files, MockResponse};
+use giphy_download::{GiphyClient, GiphyError, GiphyResponse, Images, OtherRendition};
+
+const PAGE: &str = include_str!("fixtures/channel_1234_page1.json");
+
+#[test]
+fn renditions_are_typed() {
+    let page: GiphyResponse = serde_json::from_str(PAGE).unwrap();
+    let original = page.results[0].images.original.as_ref().unwrap();
+
+    assert_eq!(original.width, Some(480));
+    assert_eq!(original.height, Some(270));
+    assert_eq!(original.frames, Some(42));
+    assert_eq!(original.webp_size, Some(21));
+    assert_eq!(
+        original.url(Some("webp")),
+        Some("https://media.giphy.com/media/aBcDeF123/giphy.webp")
+    );
+    assert_eq!(original.pixels(), Some(480 * 270));
+}
+
+#[test]
+fn unknown_renditions_are_preserved() {
+    let images: Images = serde_json::from_str(
+        r#"{
+            "source": {"url": "https://media.giphy.com/media/x/source.mp4", "size": ""},
+            "480w_still": {"url": "https://media.giphy.com/media/x/480w_s.jpg", "width": 480, "extra": "kept"}
+        }"#,
+    )
+    .unwrap();
+
+    let still = images.get("480w_still").unwrap();
+    assert_eq!(still.width, Some(480));
+    assert_eq!(still.other["extra"], "kept");
+    assert_eq!(images.source.as_ref().unwrap().size, None);
+    assert_eq!(images.iter().count(), 2);
+}
+
+#[test]
+fn missing_rendition_is_an_error() {
+    let images: Images = serde_json::from_str("{}").unwrap();
+
+    assert!(matches!(
+        images.rendition("source"),
+        Err(GiphyError::MissingRendition { .. })
+    ));
+}
+
+#[test]
+fn renditions_can_be_picked_by_size() {
+    let page: GiphyResponse = serde_json::from_str(PAGE).unwrap();
+
+    let (name, _) = page.results[0]
+        .images
+        .iter()
+        .filter(|(_, r)| r.size(None).is_some())
+        .min_by_key(|(_, r)| r.size(None))
+        .unwrap();
+
+    assert_eq!(name, "original");
+}
+
+#[test]
+fn malformed_renditions_are_skipped() {
+    let images: Images = serde_json::from_str(
+        r#"{
+            "source": {"url": "https://media.giphy.com/media/x/source.mp4", "width": "", "height": "tall"},
+            "original": "unavailable",
+            "4k": null
+        }"#,
+    )
+    .unwrap();
+
+    let source = images.source.as_ref().unwrap();
+    assert_eq!((source.width, source.height), (None, None));
+    assert!(images.original.is_none());
+    assert!(matches!(
+        images.other["4k"],
+        OtherRendition::Unknown(serde_json::Value::Null)
+    ));
+    assert_eq!(images.iter().count(), 1);
+}
+
+#[tokio::test]
+async fn malformed_renditions_dont_stop_the_page() {
+    let server = giphy_mock("").await;
+    let mut page: serde_json::Value =
+        serde_json::from_str(&PAGE.replace("https://media.giphy.com", &server.url())).unwrap();
+    let images = &mut page["results"][1]["images"];
+    images["source"]["width"] = "".into();
+    images["original"] = "unavailable".into();
+    images["4k"] = serde_json::Value::Null;
+    let feed = "/api/v4/channels/1234/feed";
+    server.reset_route(feed);
+    server.route(feed, MockResponse::ok(page.to_string()));
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    assert_eq!(media_files(dir.path().join("mockchannel")).len(), 3);
+}
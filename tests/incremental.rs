@@ -0,0 +1,82 @@
+mod common;
+
+use common::{giphy_mock, media_files};
+use giphy_download::{ArchiveOptions, Checkpoint, GiphyClient};
+
+const NEXT_PAGE: &str = "/api/v4/channels/1234/feed/?offset=2";
+
+fn incremental(threshold: usize) -> ArchiveOptions {
+    ArchiveOptions {
+        incremental: Some(threshold),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn incremental_stops_at_known_gifs() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    client
+        .archive(1234, dir.path(), &ArchiveOptions::default())
+        .await
+        .unwrap();
+    client
+        .archive(1234, dir.path(), &incremental(2))
+        .await
+        .unwrap();
+
+    assert_eq!(server.request_count(NEXT_PAGE), 1);
+    let checkpoint = Checkpoint::load(dir.path(), 1234).await.unwrap().unwrap();
+    assert_eq!(checkpoint.cursor, None);
+}
+
+#[tokio::test]
+async fn incremental_continues_past_missing_gifs() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    client
+        .archive(1234, dir.path(), &ArchiveOptions::default())
+        .await
+        .unwrap();
+    std::fs::remove_file(
+        dir.path()
+            .join("mockchannel")
+            .join("20220301_mockchannel_000310000001_GhIjKl456.gif"),
+    )
+    .unwrap();
+    client
+        .archive(1234, dir.path(), &incremental(2))
+        .await
+        .unwrap();
+
+    assert_eq!(server.request_count(NEXT_PAGE), 2);
+    assert_eq!(server.request_count("/media/GhIjKl456/source.gif"), 2);
+}
+
+#[tokio::test]
+async fn incremental_on_empty_archive_fetches_everything() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    client
+        .archive(1234, dir.path(), &incremental(1))
+        .await
+        .unwrap();
+
+    assert_eq!(server.request_count(NEXT_PAGE), 1);
+    assert_eq!(media_files(dir.path().join("mockchannel")).len(), 3);
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use common::{giphy_mock, media_body};
+use giphy_download::{
+    digest_file, GiphyClient, Manifest, RenditionLayout, RenditionSelection, RenditionSpec,
+};
+
+const FILE: &str = "20220314_mockchannel_000310000002_aBcDeF123.mp4";
+
+#[tokio::test]
+async fn manifest_lists_archived_gifs() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    let member_dir = dir.path().join("mockchannel");
+    let manifest = Manifest::load(&member_dir).await.unwrap();
+    assert_eq!(manifest.len(), 3);
+    let entry = manifest.get(FILE).unwrap();
+    assert_eq!(entry.id, "aBcDeF123");
+    assert_eq!(entry.index_id, Some(310000002));
+    assert_eq!(entry.title, "Waving Hello GIF by Mock Channel");
+    assert_eq!(entry.create_time, "2022-03-14T09:26:53+0000");
+    assert_eq!(entry.rendition, "source");
+    assert_eq!(
+        entry.sha256,
+        Some(digest_file(member_dir.join(FILE)).await.unwrap().sha256)
+    );
+}
+
+#[tokio::test]
+async fn existing_files_are_added_without_download() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let member_dir = dir.path().join("mockchannel");
+    std::fs::create_dir_all(&member_dir).unwrap();
+    std::fs::write(
+        member_dir.join(FILE),
+        media_body("/media/aBcDeF123/source.mp4"),
+    )
+    .unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    assert_eq!(server.request_count("/media/aBcDeF123/source.mp4"), 0);
+    let manifest = Manifest::load(&member_dir).await.unwrap();
+    let entry = manifest.get(FILE).unwrap();
+    assert_eq!(entry.sha256, None);
+    assert_eq!(
+        entry.size,
+        Some(media_body("/media/aBcDeF123/source.mp4").len() as u64)
+    );
+}
+
+#[tokio::test]
+async fn missing_files_are_downloaded_again() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+    std::fs::remove_file(dir.path().join("mockchannel").join(FILE)).unwrap();
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    assert_eq!(server.request_count("/media/aBcDeF123/source.mp4"), 2);
+    assert_eq!(server.request_count("/media/GhIjKl456/source.gif"), 1);
+}
+
+#[tokio::test]
+async fn manifest_filenames_include_rendition_subfolders() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .renditions(RenditionSelection {
+            renditions: vec![RenditionSpec::new("source"), RenditionSpec::new("original")],
+            all: Some(RenditionLayout::Subfolder),
+        })
+        .build()
+        .unwrap();
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    let manifest = Manifest::load(dir.path().join("mockchannel"))
+        .await
+        .unwrap();
+    assert_eq!(manifest.len(), 6);
+    let entry = manifest
+        .get("original/20220314_mockchannel_000310000002_aBcDeF123.gif")
+        .unwrap();
+    assert_eq!(entry.rendition, "original");
+}
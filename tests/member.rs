@@ -0,0 +1,104 @@
+mod common;
+
+use std::process::Command;
+
+use common::{giphy_mock, media_files};
+use giphy_download::{GiphyClient, GiphyError, MemberRef, RetryPolicy};
+
+#[test]
+fn member_refs_parse_ids_usernames_and_urls() {
+    let parse = |s: &str| s.parse::<MemberRef>().unwrap();
+    assert_eq!(parse("1234"), MemberRef::Id(1234));
+    assert_eq!(
+        parse("mockchannel"),
+        MemberRef::Username("mockchannel".to_owned())
+    );
+    assert_eq!(
+        parse("https://giphy.com/channel/mockchannel"),
+        MemberRef::Username("mockchannel".to_owned())
+    );
+    assert_eq!(
+        parse("giphy.com/mockchannel/"),
+        MemberRef::Username("mockchannel".to_owned())
+    );
+    assert!("https://giphy.com/".parse::<MemberRef>().is_err());
+    assert!("../mockchannel".parse::<MemberRef>().is_err());
+    assert!("example.com/mockchannel".parse::<MemberRef>().is_err());
+    assert!("https://giphy.com/gifs/waving-hello-aBcDeF123"
+        .parse::<MemberRef>()
+        .is_err());
+    for path in ["gifs", "stickers", "search", "explore", "embed", "channel"] {
+        assert!(format!("giphy.com/{}", path).parse::<MemberRef>().is_err());
+        assert!(format!("https://giphy.com/{}/", path)
+            .parse::<MemberRef>()
+            .is_err());
+    }
+    assert!("giphy.com/channel/mockchannel/extra"
+        .parse::<MemberRef>()
+        .is_err());
+}
+
+#[tokio::test]
+async fn usernames_resolve_to_exact_channel() {
+    let server = giphy_mock("").await;
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let member = "giphy.com/channel/mockchannel".parse().unwrap();
+    assert_eq!(client.resolve_member(&member).await.unwrap(), 1234);
+    assert_eq!(client.resolve_member(&MemberRef::Id(42)).await.unwrap(), 42);
+    assert_eq!(
+        server.request_count("/api/v4/channels/search?q=mockchannel"),
+        1
+    );
+}
+
+#[tokio::test]
+async fn unknown_username_is_an_error() {
+    let server = giphy_mock("").await;
+    server.route(
+        "/api/v4/channels/search?q=nobody",
+        common::MockResponse::ok(r#"{"next": null, "results": []}"#),
+    );
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .retry(RetryPolicy::none())
+        .build()
+        .unwrap();
+
+    let err = client
+        .resolve_member(&MemberRef::Username("nobody".to_owned()))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref(),
+        Some(GiphyError::UnknownMember { .. })
+    ));
+}
+
+#[tokio::test]
+async fn cli_accepts_channel_url() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+
+    let status = tokio::task::spawn_blocking({
+        let url = server.url();
+        let dir = dir.path().to_owned();
+        move || {
+            Command::new(env!("CARGO_BIN_EXE_giphy-download"))
+                .args(["sync", "--member", "https://giphy.com/channel/mockchannel"])
+                .arg("--directory")
+                .arg(dir)
+                .env("GIPHY_BASE_URL", url)
+                .status()
+                .unwrap()
+        }
+    })
+    .await
+    .unwrap();
+
+    assert!(status.success());
+    assert_eq!(media_files(dir.path().join("mockchannel")).len(), 3);
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use std::process::Command;
+
+use common::{giphy_mock, MockResponse, MockServer};
+use giphy_download::{ArchiveSummary, GiphyClient, RetryPolicy, Source};
+
+/// Serve a second member whose feed is a single page
+fn route_member_5678(server: &MockServer) {
+    let page = include_str!("fixtures/channel_1234_page2.json")
+        .replace("https://media.giphy.com", &server.url());
+    server.route("/api/v4/channels/5678/feed", MockResponse::ok(page));
+}
+
+#[tokio::test]
+async fn members_are_archived_with_a_shared_client() {
+    let server = giphy_mock("").await;
+    route_member_5678(&server);
+    server.route("/api/v4/channels/999/feed", MockResponse::status(404));
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .retry(RetryPolicy::none())
+        .concurrency(1)
+        .build()
+        .unwrap();
+
+    let sources = [1234, 999, 5678].map(Source::Channel);
+    let results = client
+        .archive_sources(&sources, dir.path(), &Default::default())
+        .await;
+
+    let members: Vec<_> = results.iter().map(|(source, _)| source.clone()).collect();
+    assert_eq!(members, sources);
+    assert_eq!(
+        *results[0].1.as_ref().unwrap(),
+        ArchiveSummary {
+            archived: 3,
+            failed: 0
+        }
+    );
+    assert!(results[1].1.is_err());
+    assert_eq!(results[2].1.as_ref().unwrap().archived, 1);
+}
+
+#[tokio::test]
+async fn cli_reads_members_file() {
+    let server = giphy_mock("").await;
+    route_member_5678(&server);
+    let dir = tempfile::tempdir().unwrap();
+    let members_file = dir.path().join("members.txt");
+    std::fs::write(&members_file, "# Channels\n5678  # second\n\n1234\n").unwrap();
+
+    let output = tokio::task::spawn_blocking({
+        let url = server.url();
+        let dir = dir.path().to_owned();
+        move || {
+            Command::new(env!("CARGO_BIN_EXE_giphy-download"))
+                .args(["sync", "--member", "1234", "--members-file"])
+                .arg(&members_file)
+                .arg("--directory")
+                .arg(dir)
+                .env("GIPHY_BASE_URL", url)
+                .output()
+                .unwrap()
+        }
+    })
+    .await
+    .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let summary = stdout.split_once("Summary:\n").unwrap().1;
+    assert_eq!(
+        summary,
+        "  channel:1234: 3 archived, 0 failed\n  channel:5678: 1 archived, 0 failed\n"
+    );
+    assert_eq!(server.request_count("/api/v4/channels/1234/feed"), 1);
+}
+
+#[test]
+fn cli_rejects_invalid_members_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let members_file = dir.path().join("members.txt");
+    std::fs::write(&members_file, "1234\nnot a member\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_giphy-download"))
+        .args(["sync", "--members-file"])
+        .arg(&members_file)
+        .arg("--directory")
+        .arg(dir.path())
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("line 2"));
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use std::process::Command;
+
+use common::{giphy_mock, media_body, media_files, MockResponse};
+use futures::TryStreamExt;
+use giphy_download::{GiphyClient, RetryPolicy};
+
+#[tokio::test]
+async fn gifs_follow_pagination_on_mock_server() {
+    let server = giphy_mock("").await;
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let gifs = client.gifs(1234).try_collect::<Vec<_>>().await.unwrap();
+
+    let ids: Vec<_> = gifs.iter().map(|g| g.id.as_str()).collect();
+    assert_eq!(ids, ["aBcDeF123", "GhIjKl456", "MnOpQr789"]);
+    assert_eq!(server.request_count("/api/v4/channels/1234/feed"), 1);
+    assert_eq!(
+        server.request_count("/api/v4/channels/1234/feed/?offset=2"),
+        1
+    );
+}
+
+#[tokio::test]
+async fn base_url_path_prefix_applies_to_next_links() {
+    let server = giphy_mock("/proxy").await;
+    let client = GiphyClient::builder()
+        .base_url(format!("{}/proxy", server.url()))
+        .build()
+        .unwrap();
+
+    let gifs = client.gifs(1234).try_collect::<Vec<_>>().await.unwrap();
+
+    assert_eq!(gifs.len(), 3);
+    assert_eq!(
+        server.request_count("/proxy/api/v4/channels/1234/feed/?offset=2"),
+        1
+    );
+}
+
+#[tokio::test]
+async fn download_saves_source_media() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    let path = dir
+        .path()
+        .join("mockchannel")
+        .join("20220314_mockchannel_000310000002_aBcDeF123.mp4");
+    assert_eq!(
+        std::fs::read(path).unwrap(),
+        media_body("/media/aBcDeF123/source.mp4")
+    );
+    assert_eq!(media_files(dir.path().join("mockchannel")).len(), 3);
+}
+
+#[tokio::test]
+async fn download_starts_before_feed_is_exhausted() {
+    let server = giphy_mock("").await;
+    let next_page = "/api/v4/channels/1234/feed/?offset=2";
+    server.reset_route(next_page);
+    server.route(next_page, MockResponse::status(500));
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .retry(RetryPolicy::none())
+        .build()
+        .unwrap();
+
+    let result = client.download(client.gifs(1234), dir.path()).await;
+
+    assert!(result.is_err());
+    assert_eq!(media_files(dir.path().join("mockchannel")).len(), 2);
+}
+
+#[tokio::test]
+async fn cli_reads_base_url_from_env() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+
+    let status = tokio::task::spawn_blocking({
+        let url = server.url();
+        let dir = dir.path().to_owned();
+        move || {
+            Command::new(env!("CARGO_BIN_EXE_giphy-download"))
+                .args(["sync", "--member", "1234", "--directory"])
+                .arg(dir)
+                .env("GIPHY_BASE_URL", url)
+                .status()
+                .unwrap()
+        }
+    })
+    .await
+    .unwrap();
+
+    assert!(status.success());
+    assert_eq!(media_files(dir.path().join("mockchannel")).len(), 3);
+}
+
+#[tokio::test]
+async fn large_media_is_written_completely() {
+    let server = giphy_mock("").await;
+    let media = "/media/MnOpQr789/source.mov";
+    let body: Vec<u8> = (0..8 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+    server.reset_route(media);
+    server.route(media, MockResponse::ok(body.clone()));
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    let path = dir
+        .path()
+        .join("mockchannel")
+        .join("20211231_mockchannel_000300000000_MnOpQr789.mov");
+    assert_eq!(std::fs::read(path).unwrap(), body);
+}
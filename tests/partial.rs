@@ -0,0 +1,74 @@
+mod common;
+
+use common::{giphy_mock, media_body, MockResponse};
+use giphy_download::{ArchiveOptions, GiphyClient, RetryPolicy};
+
+const FILE: &str = "20220314_mockchannel_000310000002_aBcDeF123.mp4";
+
+#[tokio::test]
+async fn stale_part_file_is_replaced() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let member_dir = dir.path().join("mockchannel");
+    std::fs::create_dir_all(&member_dir).unwrap();
+    std::fs::write(member_dir.join(format!("{}.part", FILE)), b"trunc").unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    client
+        .archive(1234, dir.path(), &ArchiveOptions::default())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        std::fs::read(member_dir.join(FILE)).unwrap(),
+        media_body("/media/aBcDeF123/source.mp4")
+    );
+    assert!(!member_dir.join(format!("{}.part", FILE)).exists());
+}
+
+#[tokio::test]
+async fn failed_download_leaves_no_final_file() {
+    let server = giphy_mock("").await;
+    server.route_once("/media/aBcDeF123/source.mp4", MockResponse::status(500));
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .retry(RetryPolicy::none())
+        .build()
+        .unwrap();
+
+    client
+        .archive(1234, dir.path(), &ArchiveOptions::default())
+        .await
+        .unwrap();
+
+    assert!(!dir.path().join("mockchannel").join(FILE).exists());
+}
+
+#[tokio::test]
+async fn part_files_do_not_count_as_archived() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let member_dir = dir.path().join("mockchannel");
+    std::fs::create_dir_all(&member_dir).unwrap();
+    std::fs::write(member_dir.join(format!("{}.part", FILE)), b"trunc").unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+    let options = ArchiveOptions {
+        incremental: Some(1),
+        ..Default::default()
+    };
+
+    client.archive(1234, dir.path(), &options).await.unwrap();
+
+    assert!(member_dir.join(FILE).exists());
+    assert_eq!(
+        server.request_count("/api/v4/channels/1234/feed/?offset=2"),
+        1
+    );
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{giphy_mock, MockResponse};
+use giphy_download::{digest_file, GiphyClient, RetryPolicy, Sidecar};
+
+const MEDIA: &str = "/media/MnOpQr789/source.mov";
+const FILE: &str = "20211231_mockchannel_000300000000_MnOpQr789.mov";
+
+fn body() -> Vec<u8> {
+    (0..256 * 1024).map(|i| (i % 251) as u8).collect()
+}
+
+fn client(url: String) -> GiphyClient {
+    GiphyClient::builder()
+        .base_url(url)
+        .retry(RetryPolicy {
+            max_attempts: 2,
+            base_delay: Duration::from_millis(1),
+            ..Default::default()
+        })
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn interrupted_download_resumes_with_range() {
+    let server = giphy_mock("").await;
+    let media = MockResponse::ok(body())
+        .header("Accept-Ranges", "bytes")
+        .header("ETag", "\"v1\"");
+    server.reset_route(MEDIA);
+    server.route(MEDIA, media.clone().truncate(100_000));
+    server.route(MEDIA, media);
+    let dir = tempfile::tempdir().unwrap();
+    let client = client(server.url());
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    let headers = server.request_headers(MEDIA);
+    assert_eq!(headers.len(), 2);
+    assert_eq!(headers[1]["range"], "bytes=100000-");
+    assert_eq!(headers[1]["if-range"], "\"v1\"");
+    let member_dir = dir.path().join("mockchannel");
+    assert_eq!(std::fs::read(member_dir.join(FILE)).unwrap(), body());
+    assert!(!member_dir.join(format!("{}.part.json", FILE)).exists());
+}
+
+#[tokio::test]
+async fn changed_resource_is_downloaded_again() {
+    let server = giphy_mock("").await;
+    server.reset_route(MEDIA);
+    server.route(
+        MEDIA,
+        MockResponse::ok(body())
+            .header("Accept-Ranges", "bytes")
+            .header("ETag", "\"v1\"")
+            .truncate(100_000),
+    );
+    server.route(
+        MEDIA,
+        MockResponse::ok(body())
+            .header("Accept-Ranges", "bytes")
+            .header("ETag", "\"v2\""),
+    );
+    let dir = tempfile::tempdir().unwrap();
+    let client = client(server.url());
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    let headers = server.request_headers(MEDIA);
+    assert_eq!(headers[1]["if-range"], "\"v1\"");
+    let path = dir.path().join("mockchannel").join(FILE);
+    assert_eq!(std::fs::read(path).unwrap(), body());
+}
+
+#[tokio::test]
+async fn no_range_request_without_accept_ranges() {
+    let server = giphy_mock("").await;
+    server.reset_route(MEDIA);
+    server.route(
+        MEDIA,
+        MockResponse::ok(body())
+            .header("ETag", "\"v1\"")
+            .truncate(100_000),
+    );
+    server.route(MEDIA, MockResponse::ok(body()).header("ETag", "\"v1\""));
+    let dir = tempfile::tempdir().unwrap();
+    let client = client(server.url());
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    let headers = server.request_headers(MEDIA);
+    assert_eq!(headers.len(), 2);
+    assert!(!headers[1].contains_key("range"));
+    let path = dir.path().join("mockchannel").join(FILE);
+    assert_eq!(std::fs::read(path).unwrap(), body());
+}
+
+#[tokio::test]
+async fn resumed_download_digest_covers_whole_file() {
+    let server = giphy_mock("").await;
+    let media = MockResponse::ok(body())
+        .header("Accept-Ranges", "bytes")
+        .header("ETag", "\"v1\"");
+    server.reset_route(MEDIA);
+    server.route(MEDIA, media.clone().truncate(100_000));
+    server.route(MEDIA, media);
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .retry(RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            ..Default::default()
+        })
+        .sidecars(true)
+        .build()
+        .unwrap();
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    let path = dir.path().join("mockchannel").join(FILE);
+    let sidecar = Sidecar::load(&path).await.unwrap();
+    assert_eq!(sidecar.download.size, body().len() as u64);
+    assert_eq!(
+        sidecar.download.sha256,
+        digest_file(&path).await.unwrap().sha256
+    );
+}
@@ -0,0 +1,155 @@
+mod common;
+
+use common::{giphy_mock, media_body, media_files, MockResponse};
+use giphy_download::{GiphyClient, GiphyError, RenditionLayout, RenditionSelection, RenditionSpec};
+
+fn client(url: String, renditions: &str, all: Option<RenditionLayout>) -> GiphyClient {
+    GiphyClient::builder()
+        .base_url(url)
+        .renditions(RenditionSelection {
+            renditions: renditions.split(',').map(|r| r.parse().unwrap()).collect(),
+            all,
+        })
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn rendition_spec_parses_format() {
+    let spec: RenditionSpec = "fixed_height:webp".parse().unwrap();
+    assert_eq!(spec.name, "fixed_height");
+    assert_eq!(spec.format.as_deref(), Some("webp"));
+    assert_eq!(spec.label(), "fixed_height-webp");
+    assert!("../source".parse::<RenditionSpec>().is_err());
+}
+
+#[test]
+fn rendition_layout_parses() {
+    assert_eq!(
+        "subfolder".parse::<RenditionLayout>().unwrap(),
+        RenditionLayout::Subfolder
+    );
+    assert!(matches!(
+        "folders".parse::<RenditionLayout>(),
+        Err(GiphyError::InvalidLayout { .. })
+    ));
+}
+
+#[tokio::test]
+async fn first_available_rendition_is_used() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = client(server.url(), "original_mp4,original", None);
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    let member_dir = dir.path().join("mockchannel");
+    assert_eq!(
+        std::fs::read(member_dir.join("20220314_mockchannel_000310000002_aBcDeF123.mp4")).unwrap(),
+        media_body("/media/aBcDeF123/giphy-original.mp4")
+    );
+    assert_eq!(
+        std::fs::read(member_dir.join("20220301_mockchannel_000310000001_GhIjKl456.gif")).unwrap(),
+        media_body("/media/GhIjKl456/giphy.gif")
+    );
+    assert_eq!(server.request_count("/media/aBcDeF123/source.mp4"), 0);
+}
+
+#[tokio::test]
+async fn all_renditions_in_subfolders() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = client(
+        server.url(),
+        "source,original:webp",
+        Some(RenditionLayout::Subfolder),
+    );
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    let member_dir = dir.path().join("mockchannel");
+    let name = "20220314_mockchannel_000310000002_aBcDeF123";
+    assert!(member_dir
+        .join("source")
+        .join(format!("{}.mp4", name))
+        .exists());
+    assert_eq!(
+        std::fs::read(
+            member_dir
+                .join("original-webp")
+                .join(format!("{}.webp", name))
+        )
+        .unwrap(),
+        media_body("/media/aBcDeF123/giphy.webp")
+    );
+}
+
+#[tokio::test]
+async fn failed_rendition_doesnt_skip_the_rest() {
+    let server = giphy_mock("").await;
+    let media = "/media/aBcDeF123/source.mp4";
+    server.reset_route(media);
+    server.route(media, MockResponse::status(404));
+    let dir = tempfile::tempdir().unwrap();
+    let client = client(
+        server.url(),
+        "source,original:webp",
+        Some(RenditionLayout::Subfolder),
+    );
+
+    let gif = client.gif(&"aBcDeF123".parse().unwrap()).await.unwrap();
+    client.download_gif(gif, dir.path()).await.unwrap_err();
+
+    let name = "20220314_mockchannel_000310000002_aBcDeF123";
+    assert!(dir
+        .path()
+        .join("mockchannel")
+        .join("original-webp")
+        .join(format!("{}.webp", name))
+        .exists());
+}
+
+#[tokio::test]
+async fn all_renditions_with_suffix() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = client(
+        server.url(),
+        "source,original_mp4",
+        Some(RenditionLayout::Suffix),
+    );
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    let member_dir = dir.path().join("mockchannel");
+    let name = "20220314_mockchannel_000310000002_aBcDeF123";
+    assert!(member_dir.join(format!("{}.source.mp4", name)).exists());
+    assert!(member_dir
+        .join(format!("{}.original_mp4.mp4", name))
+        .exists());
+    // Only the first GIF has an original_mp4 rendition
+    assert_eq!(media_files(&member_dir).len(), 4);
+}
+
+#[tokio::test]
+async fn missing_renditions_fail_the_gif() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = client(server.url(), "hd", None);
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    assert!(!dir.path().join("mockchannel").exists());
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{giphy_mock, media_files, MockResponse};
+use futures::TryStreamExt;
+use giphy_download::{ArchiveOptions, Checkpoint, GifStatus, GiphyClient, RetryPolicy};
+
+const NEXT_PAGE: &str = "/api/v4/channels/1234/feed/?offset=2";
+
+fn fast_retry() -> RetryPolicy {
+    RetryPolicy {
+        max_attempts: 3,
+        base_delay: Duration::from_millis(1),
+        jitter: false,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn transient_feed_errors_are_retried() {
+    let server = giphy_mock("").await;
+    server.route_once(NEXT_PAGE, MockResponse::status(502));
+    server.route_once(NEXT_PAGE, MockResponse::status(500));
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .retry(fast_retry())
+        .build()
+        .unwrap();
+
+    let gifs = client.gifs(1234).try_collect::<Vec<_>>().await.unwrap();
+
+    assert_eq!(gifs.len(), 3);
+    assert_eq!(server.request_count(NEXT_PAGE), 3);
+}
+
+#[tokio::test]
+async fn retries_give_up_after_max_attempts() {
+    let server = giphy_mock("").await;
+    server.reset_route(NEXT_PAGE);
+    server.route(NEXT_PAGE, MockResponse::status(503));
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .retry(fast_retry())
+        .build()
+        .unwrap();
+
+    let result = client.gifs(1234).try_collect::<Vec<_>>().await;
+
+    assert!(result.is_err());
+    assert_eq!(server.request_count(NEXT_PAGE), 3);
+}
+
+#[tokio::test]
+async fn permanent_errors_are_not_retried() {
+    let server = giphy_mock("").await;
+    server.route_once(NEXT_PAGE, MockResponse::status(410));
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .retry(fast_retry())
+        .build()
+        .unwrap();
+
+    let result = client.gifs(1234).try_collect::<Vec<_>>().await;
+
+    assert!(result.is_err());
+    assert_eq!(server.request_count(NEXT_PAGE), 1);
+}
+
+#[tokio::test]
+async fn retry_after_is_honored() {
+    let server = giphy_mock("").await;
+    server.route_once(
+        NEXT_PAGE,
+        MockResponse::status(429).header("Retry-After", "1"),
+    );
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .retry(fast_retry())
+        .build()
+        .unwrap();
+
+    let start = Instant::now();
+    let gifs = client.gifs(1234).try_collect::<Vec<_>>().await.unwrap();
+
+    assert_eq!(gifs.len(), 3);
+    assert!(start.elapsed() >= Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn media_downloads_are_retried() {
+    let server = giphy_mock("").await;
+    let media = "/media/aBcDeF123/source.mp4";
+    server.route_once(media, MockResponse::status(500));
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .retry(fast_retry())
+        .build()
+        .unwrap();
+
+    client
+        .archive(1234, dir.path(), &ArchiveOptions::default())
+        .await
+        .unwrap();
+
+    assert_eq!(server.request_count(media), 2);
+    let checkpoint = Checkpoint::load(dir.path(), 1234).await.unwrap().unwrap();
+    assert_eq!(checkpoint.gifs.get("aBcDeF123"), Some(&GifStatus::Done));
+}
+
+#[tokio::test]
+async fn missing_media_is_recorded_as_failed() {
+    let server = giphy_mock("").await;
+    let media = "/media/GhIjKl456/source.gif";
+    server.route_once(media, MockResponse::status(404));
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .retry(fast_retry())
+        .build()
+        .unwrap();
+
+    client
+        .archive(1234, dir.path(), &ArchiveOptions::default())
+        .await
+        .unwrap();
+
+    assert_eq!(server.request_count(media), 1);
+    let checkpoint = Checkpoint::load(dir.path(), 1234).await.unwrap().unwrap();
+    assert_eq!(checkpoint.gifs.get("GhIjKl456"), Some(&GifStatus::Failed));
+    assert_eq!(media_files(dir.path().join("mockchannel")).len(), 2);
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use common::{giphy_mock, media_body};
+use giphy_download::{
+    sanitize_component, sanitize_path, GiphyClient, GiphyResponse, MAX_COMPONENT_BYTES,
+};
+
+#[test]
+fn components_are_safe_on_windows_shares() {
+    assert_eq!(sanitize_component("a/b\\c:d*e?.gif"), "a_b_c_d_e_.gif");
+    assert_eq!(sanitize_component("..."), "_");
+    assert_eq!(sanitize_component("trailing. "), "trailing");
+    assert_eq!(sanitize_component("nul.mp4"), "_nul.mp4");
+    assert_eq!(sanitize_component("Com1"), "_Com1");
+    assert_eq!(sanitize_component("console.mp4"), "console.mp4");
+    assert_eq!(sanitize_component("a\u{202E}b\u{200B}c\td"), "abc_d");
+    // Decomposed e + combining acute accent becomes a single character
+    assert_eq!(sanitize_component("cafe\u{301}"), "caf\u{e9}");
+}
+
+#[test]
+fn long_components_keep_their_extension() {
+    let name = format!("{}.mp4", "\u{e9}".repeat(300));
+    let truncated = sanitize_component(&name);
+    assert!(truncated.len() <= MAX_COMPONENT_BYTES);
+    assert!(truncated.ends_with("\u{e9}.mp4"));
+}
+
+#[test]
+fn paths_cannot_escape_their_directory() {
+    assert_eq!(sanitize_path("../../etc/passwd"), "_/_/etc/passwd");
+    assert_eq!(sanitize_path("/abs//path/"), "abs/path");
+    assert_eq!(sanitize_path("a\\..\\b"), "a/_/b");
+    assert_eq!(sanitize_path(""), "_");
+}
+
+#[tokio::test]
+async fn hostile_feed_values_stay_inside_download_dir() {
+    let server = giphy_mock("").await;
+    let page = include_str!("fixtures/channel_1234_page1.json")
+        .replace("https://media.giphy.com", &server.url());
+    let mut gif = serde_json::from_str::<GiphyResponse>(&page)
+        .unwrap()
+        .results
+        .remove(0);
+    gif.user.as_mut().unwrap().username = "../..".to_owned();
+    gif.title = "con".to_owned();
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .filename_template("{title}.{ext}".parse().unwrap())
+        .build()
+        .unwrap();
+
+    client
+        .download_gif(gif, dir.path().join("archive"))
+        .await
+        .unwrap();
+
+    let path = dir.path().join("archive/.._/_con.mp4");
+    assert_eq!(
+        std::fs::read(path).unwrap(),
+        media_body("/media/aBcDeF123/source.mp4")
+    );
+    let entries: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+    assert_eq!(entries.len(), 1);
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use common::{giphy_mock, media_body};
+use giphy_download::{digest_file, verify_archive, GiphyClient, Sidecar};
+
+const FILE: &str = "20220314_mockchannel_000310000002_aBcDeF123.mp4";
+
+#[tokio::test]
+async fn sidecar_has_feed_entry_and_download_facts() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .sidecars(true)
+        .build()
+        .unwrap();
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    let path = dir.path().join("mockchannel").join(FILE);
+    let sidecar = Sidecar::load(&path).await.unwrap();
+    assert_eq!(sidecar.gif["title"], "Waving Hello GIF by Mock Channel");
+    assert_eq!(sidecar.gif["create_datetime"], "2022-03-14T09:26:53+0000");
+    assert_eq!(sidecar.gif["user"]["name"], "Mock Channel");
+    assert_eq!(sidecar.download.rendition, "source");
+    assert!(sidecar
+        .download
+        .source_url
+        .ends_with("/media/aBcDeF123/source.mp4"));
+    assert_eq!(
+        sidecar.download.size,
+        media_body("/media/aBcDeF123/source.mp4").len() as u64
+    );
+    assert_eq!(
+        sidecar.download.sha256,
+        digest_file(&path).await.unwrap().sha256
+    );
+
+    let raw: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(Sidecar::path(&path)).unwrap()).unwrap();
+    assert_eq!(raw["gif"]["images"]["original"]["frames"], "42");
+}
+
+#[tokio::test]
+async fn sidecars_are_added_to_existing_files() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+    let path = dir.path().join("mockchannel").join(FILE);
+    assert!(!Sidecar::path(&path).exists());
+
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .sidecars(true)
+        .build()
+        .unwrap();
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    let sidecar = Sidecar::load(&path).await.unwrap();
+    let digest = digest_file(&path).await.unwrap();
+    assert_eq!(sidecar.download.sha256, digest.sha256);
+    assert_eq!(sidecar.download.size, digest.size);
+    assert_eq!(server.request_count("/media/aBcDeF123/source.mp4"), 1);
+}
+
+#[tokio::test]
+async fn no_sidecars_by_default() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    assert!(!Sidecar::path(dir.path().join("mockchannel").join(FILE)).exists());
+}
+
+#[tokio::test]
+async fn backfilled_sidecars_keep_the_recorded_digest() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+    let path = dir.path().join("mockchannel").join(FILE);
+    let archived = digest_file(&path).await.unwrap();
+    let mut modified = std::fs::read(&path).unwrap();
+    modified[0] ^= 1;
+    std::fs::write(&path, modified).unwrap();
+
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .sidecars(true)
+        .build()
+        .unwrap();
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    let sidecar = Sidecar::load(&path).await.unwrap();
+    assert_eq!(sidecar.download.sha256, archived.sha256);
+    assert_eq!(verify_archive(dir.path()).await.unwrap().issues.len(), 1);
+}
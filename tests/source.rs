@@ -0,0 +1,266 @@
+mod common;
+
+use std::process::Command;
+
+use common::{giphy_mock, media_files, MockResponse, MockServer};
+use futures::TryStreamExt;
+use giphy_download::{ArchiveOptions, Catalog, Checkpoint, GiphyClient, GiphyError, Source};
+
+/// Serve the recorded first channel page as another feed, its `next` link
+/// continues with the channel's second page
+fn route_feed(server: &MockServer, path: &str) {
+    let page = include_str!("fixtures/channel_1234_page1.json")
+        .replace("https://media.giphy.com", &server.url());
+    server.route(path, MockResponse::ok(page));
+}
+
+#[test]
+fn sources_parse_and_display() {
+    for s in ["channel:1234", "search:happy dance", "tag:cats", "trending"] {
+        assert_eq!(s.parse::<Source>().unwrap().to_string(), s);
+    }
+    assert_eq!(
+        "tag:#cats".parse::<Source>().unwrap(),
+        Source::Tag("cats".to_owned())
+    );
+    assert!("search:".parse::<Source>().is_err());
+    assert!("channel:abc".parse::<Source>().is_err());
+    assert!("popular".parse::<Source>().is_err());
+}
+
+#[test]
+fn source_keys_keep_distinct_queries_apart() {
+    let key = |s: &str| s.parse::<Source>().unwrap().key();
+
+    assert_eq!(key("channel:1234"), "1234");
+    assert_eq!(key("trending"), "trending");
+    assert!(key("tag:a/b").starts_with("tag-"));
+    assert!(key("tag:a/b").ends_with("-a_b"));
+    assert_ne!(key("tag:a/b"), key("tag:a_b"));
+    assert_ne!(key("search:a/b"), key("search:a_b"));
+    assert_ne!(key("search:cats"), key("tag:cats"));
+}
+
+#[tokio::test]
+async fn long_queries_fit_the_state_file_name() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = Source::Search("é".repeat(200));
+
+    Checkpoint::new(source.key())
+        .save(dir.path())
+        .await
+        .unwrap();
+
+    let path = Checkpoint::path(dir.path(), source.key());
+    assert!(path.file_name().unwrap().len() <= 240);
+    assert_ne!(source.key(), Source::Search("é".repeat(201)).key());
+}
+
+#[tokio::test]
+async fn source_urls_encode_their_arguments() {
+    let client = GiphyClient::builder()
+        .base_url("http://localhost/proxy")
+        .build()
+        .unwrap();
+    let url = |s: &str| client.source_url(&s.parse().unwrap()).unwrap().to_string();
+
+    assert_eq!(
+        url("channel:1234"),
+        "http://localhost/proxy/api/v4/channels/1234/feed"
+    );
+    assert_eq!(
+        url("search:happy dance"),
+        "http://localhost/proxy/api/v4/search?q=happy+dance"
+    );
+    assert_eq!(
+        url("tag:a/b"),
+        "http://localhost/proxy/api/v4/tags/a%2Fb/feed"
+    );
+    assert_eq!(url("trending"), "http://localhost/proxy/api/v4/trending");
+}
+
+#[tokio::test]
+async fn tag_feed_follows_pagination() {
+    let server = giphy_mock("").await;
+    route_feed(&server, "/api/v4/tags/hello/feed");
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let gifs = client
+        .source_gifs(&Source::Tag("hello".to_owned()))
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(gifs.len(), 3);
+}
+
+#[tokio::test]
+async fn search_is_archived_with_its_own_checkpoint() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+
+    let source = Source::Search("hello".to_owned());
+
+    let summary = client
+        .archive_source(&source, dir.path(), &ArchiveOptions::default())
+        .await
+        .unwrap();
+
+    assert_eq!(summary.archived, 2);
+    assert_eq!(
+        media_files(dir.path().join("anonymous")),
+        ["20220502_anonymous_000000000000_StUvWx012.gif"]
+    );
+    assert_eq!(
+        media_files(dir.path().join("othermember")),
+        ["20220420_othermember_000000000000_YzAbCd345.mp4"]
+    );
+    let checkpoint = Checkpoint::load(dir.path(), source.key())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(checkpoint.source, source.key());
+    assert!(checkpoint.is_done("StUvWx012"));
+}
+
+#[tokio::test]
+async fn anonymous_tag_gifs_are_cataloged() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .catalog(Catalog::open_in_memory().unwrap())
+        .build()
+        .unwrap();
+
+    client
+        .archive_source(
+            &Source::Tag("waving".to_owned()),
+            dir.path(),
+            &ArchiveOptions::default(),
+        )
+        .await
+        .unwrap();
+
+    let (index_id, member_id): (Option<u64>, Option<u64>) = client
+        .catalog()
+        .unwrap()
+        .connection()
+        .query_row(
+            "SELECT index_id, member_id FROM gifs WHERE id = 'EfGhIj678'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!((index_id, member_id), (None, None));
+    assert_eq!(
+        client.catalog().unwrap().files("EfGhIj678").unwrap().len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn only_channels_are_archived_incrementally() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .build()
+        .unwrap();
+    let options = ArchiveOptions {
+        incremental: Some(25),
+        ..Default::default()
+    };
+
+    let e = client
+        .archive_source(&Source::Trending, dir.path(), &options)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        e.downcast_ref(),
+        Some(GiphyError::IncrementalSource { .. })
+    ));
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn legacy_checkpoints_still_load() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        Checkpoint::path(dir.path(), 1234),
+        r#"{"member_id": 1234, "cursor": null, "gifs": {"aBcDeF123": "done"}}"#,
+    )
+    .unwrap();
+
+    let checkpoint = Checkpoint::load(dir.path(), 1234).await.unwrap().unwrap();
+
+    assert_eq!(checkpoint.source, "1234");
+    assert!(checkpoint.is_done("aBcDeF123"));
+}
+
+#[tokio::test]
+async fn cli_archives_trending() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+
+    let status = tokio::task::spawn_blocking({
+        let url = server.url();
+        let dir = dir.path().to_owned();
+        move || {
+            Command::new(env!("CARGO_BIN_EXE_giphy-download"))
+                .args(["sync", "--source", "trending", "--directory"])
+                .arg(dir)
+                .env("GIPHY_BASE_URL", url)
+                .status()
+                .unwrap()
+        }
+    })
+    .await
+    .unwrap();
+
+    assert!(status.success());
+    assert_eq!(media_files(dir.path().join("anonymous")).len(), 2);
+}
+
+#[tokio::test]
+async fn cli_rejects_incremental_trending() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = tokio::task::spawn_blocking({
+        let url = server.url();
+        let dir = dir.path().to_owned();
+        move || {
+            Command::new(env!("CARGO_BIN_EXE_giphy-download"))
+                .args([
+                    "sync",
+                    "-m",
+                    "1234",
+                    "--source",
+                    "trending",
+                    "--incremental",
+                ])
+                .arg("--directory")
+                .arg(dir)
+                .env("GIPHY_BASE_URL", url)
+                .output()
+                .unwrap()
+        }
+    })
+    .await
+    .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Can't archive trending incrementally"));
+    assert!(server.requests().iter().all(|r| !r.starts_with("/media/")));
+}
@@ -0,0 +1,185 @@
+mod common;
+
+use std::path::Path;
+
+use common::{giphy_mock, media_body, MockResponse, MockServer};
+use giphy_download::{
+    digest_file, verify_archive, Catalog, ContentStore, GiphyClient, GiphyError, StoreLink,
+};
+
+const FIRST: &str = "20220314_mockchannel_000310000002_aBcDeF123.mp4";
+const SECOND: &str = "20220301_mockchannel_000310000001_GhIjKl456.gif";
+
+/// Mock where the first two GIFs have the same source media
+async fn repost_mock() -> MockServer {
+    let server = giphy_mock("").await;
+    let repost = "/media/GhIjKl456/source.gif";
+    server.reset_route(repost);
+    server.route(
+        repost,
+        MockResponse::ok(media_body("/media/aBcDeF123/source.mp4")),
+    );
+    server
+}
+
+fn client(url: String, store: ContentStore) -> GiphyClient {
+    GiphyClient::builder()
+        .base_url(url)
+        .content_store(store)
+        .catalog(Catalog::open_in_memory().unwrap())
+        .build()
+        .unwrap()
+}
+
+fn count_files(dir: &Path) -> usize {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .map(|p| match p.is_dir() {
+            true => count_files(&p),
+            false => 1,
+        })
+        .sum()
+}
+
+#[tokio::test]
+async fn identical_media_is_stored_once() {
+    let server = repost_mock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let store = ContentStore::new(dir.path().join("store"), StoreLink::Hardlink);
+    let client = client(server.url(), store.clone());
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    let member_dir = dir.path().join("mockchannel");
+    let body = media_body("/media/aBcDeF123/source.mp4");
+    assert_eq!(std::fs::read(member_dir.join(FIRST)).unwrap(), body);
+    assert_eq!(std::fs::read(member_dir.join(SECOND)).unwrap(), body);
+    assert_eq!(count_files(&store.dir), 2);
+
+    let sha256 = digest_file(member_dir.join(FIRST)).await.unwrap().sha256;
+    assert!(store.object_path(&sha256).exists());
+    let references = client.catalog().unwrap().references(&sha256).unwrap();
+    assert_eq!(references.len(), 2);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let object = std::fs::metadata(store.object_path(&sha256)).unwrap();
+        assert_eq!(object.nlink(), 3);
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn symlinks_point_into_the_store() {
+    let server = repost_mock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let store = ContentStore::new(dir.path().join("store"), StoreLink::Symlink);
+    let client = client(server.url(), store.clone());
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    let path = dir.path().join("mockchannel").join(FIRST);
+    assert!(std::fs::symlink_metadata(&path)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    let sha256 = digest_file(&path).await.unwrap().sha256;
+    assert_eq!(
+        std::fs::canonicalize(&path).unwrap(),
+        std::fs::canonicalize(store.object_path(&sha256)).unwrap()
+    );
+    assert!(verify_archive(dir.path().join("mockchannel"))
+        .await
+        .unwrap()
+        .is_ok());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn dangling_symlinks_are_downloaded_again() {
+    let server = repost_mock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let store = ContentStore::new(dir.path().join("store"), StoreLink::Symlink);
+    let first = client(server.url(), store.clone());
+    first.download(first.gifs(1234), dir.path()).await.unwrap();
+    std::fs::remove_dir_all(&store.dir).unwrap();
+
+    let second = client(server.url(), store);
+    second
+        .download(second.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    assert_eq!(server.request_count("/media/aBcDeF123/source.mp4"), 2);
+    assert!(verify_archive(dir.path().join("mockchannel"))
+        .await
+        .unwrap()
+        .is_ok());
+}
+
+#[tokio::test]
+async fn damaged_objects_are_reported_not_replaced() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let store = ContentStore::new(dir.path().join("store"), StoreLink::Hardlink);
+    let client = client(server.url(), store.clone());
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+    let path = dir.path().join("mockchannel").join(FIRST);
+    let sha256 = digest_file(&path).await.unwrap().sha256;
+    let object = store.object_path(&sha256);
+    std::fs::write(&object, b"damaged").unwrap();
+
+    let gif = client.gif(&"aBcDeF123".parse().unwrap()).await.unwrap();
+    let e = client.download_gif(gif, dir.path()).await.unwrap_err();
+
+    assert!(matches!(
+        e.downcast_ref(),
+        Some(GiphyError::DamagedObject { actual: 7, .. })
+    ));
+    assert_eq!(std::fs::read(&object).unwrap(), b"damaged");
+    assert!(!dir
+        .path()
+        .join("mockchannel")
+        .join(format!("{}.part", FIRST))
+        .exists());
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn symlinked_store_can_be_on_another_filesystem() {
+    use std::os::unix::fs::MetadataExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let store_dir = match tempfile::tempdir_in("/dev/shm") {
+        Ok(d) => d,
+        Err(_) => return,
+    };
+    let dev = |p: &Path| std::fs::metadata(p).unwrap().dev();
+    if dev(dir.path()) == dev(store_dir.path()) {
+        return;
+    }
+    let server = repost_mock().await;
+    let store = ContentStore::new(store_dir.path(), StoreLink::Symlink);
+    let client = client(server.url(), store);
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    assert_eq!(count_files(store_dir.path()), 2);
+    assert!(verify_archive(dir.path().join("mockchannel"))
+        .await
+        .unwrap()
+        .is_ok());
+}
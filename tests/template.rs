@@ -0,0 +1,73 @@
+mod common;
+
+use common::{giphy_mock, media_body};
+use giphy_download::{GiphyClient, RenditionLayout, RenditionSelection, RenditionSpec, Template};
+
+#[test]
+fn template_rejects_unknown_fields() {
+    assert!("{id}.{ext}".parse::<Template>().is_ok());
+    assert!("{{literal}}_{id}".parse::<Template>().is_ok());
+    assert!("{name}.{ext}".parse::<Template>().is_err());
+    assert!("{id.{ext}".parse::<Template>().is_err());
+    assert!("{index_id:x}".parse::<Template>().is_err());
+    assert!("".parse::<Template>().is_err());
+}
+
+#[tokio::test]
+async fn downloads_follow_templates() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .dir_template("{username}/{year}/{month}".parse().unwrap())
+        .filename_template(
+            "{day}_{title}_{user_id}_{index_id:012}.{ext}"
+                .parse()
+                .unwrap(),
+        )
+        .build()
+        .unwrap();
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    let path = dir
+        .path()
+        .join("mockchannel/2022/03")
+        .join("14_Waving Hello GIF by Mock Channel_1234_000310000002.mp4");
+    assert_eq!(
+        std::fs::read(path).unwrap(),
+        media_body("/media/aBcDeF123/source.mp4")
+    );
+    assert!(dir
+        .path()
+        .join("mockchannel/2021/12")
+        .join("31_Happy New Year GIF by Mock Channel_1234_000300000000.mov")
+        .exists());
+}
+
+#[tokio::test]
+async fn suffix_layout_applies_to_templated_names() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .renditions(RenditionSelection {
+            renditions: vec![RenditionSpec::new("source")],
+            all: Some(RenditionLayout::Suffix),
+        })
+        .dir_template("all".parse().unwrap())
+        .filename_template("{id}.{ext}".parse().unwrap())
+        .build()
+        .unwrap();
+
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    assert!(dir.path().join("all/aBcDeF123.source.mp4").exists());
+    assert!(dir.path().join("all/GhIjKl456.source.gif").exists());
+}
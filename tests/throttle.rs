@@ -0,0 +1,51 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{giphy_mock, MockResponse};
+use futures::TryStreamExt;
+use giphy_download::GiphyClient;
+
+#[tokio::test]
+async fn feed_requests_are_rate_limited() {
+    let server = giphy_mock("").await;
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .requests_per_second(4.0)
+        .build()
+        .unwrap();
+
+    let start = Instant::now();
+    client.gifs(1234).try_collect::<Vec<_>>().await.unwrap();
+    client.gifs(1234).try_collect::<Vec<_>>().await.unwrap();
+
+    // The first request is free, the other three wait a quarter second each
+    assert!(start.elapsed() >= Duration::from_millis(700));
+}
+
+#[tokio::test]
+async fn media_downloads_share_a_bandwidth_cap() {
+    let server = giphy_mock("").await;
+    let media = "/media/MnOpQr789/source.mov";
+    server.reset_route(media);
+    server.route(media, MockResponse::ok(vec![0; 96 * 1024]));
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .bytes_per_second(64 * 1024)
+        .build()
+        .unwrap();
+
+    let start = Instant::now();
+    client
+        .download(client.gifs(1234), dir.path())
+        .await
+        .unwrap();
+
+    // A second worth of bytes is allowed at once, the rest takes half a second
+    assert!(start.elapsed() >= Duration::from_millis(400));
+    let path = dir
+        .path()
+        .join("mockchannel/20211231_mockchannel_000300000000_MnOpQr789.mov");
+    assert_eq!(std::fs::metadata(path).unwrap().len(), 96 * 1024);
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use std::process::Command;
+
+use common::{giphy_mock, media_body, MockResponse};
+use giphy_download::{verify_archive, FileProblem, GiphyClient, RetryPolicy};
+
+const FIRST: &str = "20220314_mockchannel_000310000002_aBcDeF123.mp4";
+const SECOND: &str = "20220301_mockchannel_000310000001_GhIjKl456.gif";
+const THIRD: &str = "20211231_mockchannel_000300000000_MnOpQr789.mov";
+
+async fn archive(url: String, dir: &std::path::Path) {
+    let client = GiphyClient::builder().base_url(url).build().unwrap();
+    client.download(client.gifs(1234), dir).await.unwrap();
+}
+
+#[tokio::test]
+async fn verify_reports_missing_truncated_and_modified_files() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    archive(server.url(), dir.path()).await;
+    let member_dir = dir.path().join("mockchannel");
+
+    assert!(verify_archive(dir.path()).await.unwrap().is_ok());
+
+    std::fs::remove_file(member_dir.join(FIRST)).unwrap();
+    std::fs::write(member_dir.join(SECOND), b"fake").unwrap();
+    let mut modified = media_body("/media/MnOpQr789/source.mov");
+    modified[0] ^= 1;
+    std::fs::write(member_dir.join(THIRD), modified).unwrap();
+    let report = verify_archive(dir.path()).await.unwrap();
+
+    assert_eq!(report.checked, 3);
+    let problems: Vec<_> = report
+        .issues
+        .iter()
+        .map(|i| (i.entry.id.as_str(), i.problem.clone()))
+        .collect();
+    assert_eq!(
+        problems,
+        [
+            ("MnOpQr789", FileProblem::Modified),
+            (
+                "GhIjKl456",
+                FileProblem::Truncated {
+                    expected: 38,
+                    actual: 4
+                }
+            ),
+            ("aBcDeF123", FileProblem::Missing),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn incomplete_existing_files_are_downloaded_again() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let member_dir = dir.path().join("mockchannel");
+    archive(server.url(), dir.path()).await;
+    std::fs::write(member_dir.join(FIRST), b"").unwrap();
+    std::fs::write(member_dir.join(SECOND), b"fake").unwrap();
+
+    archive(server.url(), dir.path()).await;
+
+    assert_eq!(server.request_count("/media/aBcDeF123/source.mp4"), 2);
+    assert_eq!(server.request_count("/media/GhIjKl456/source.gif"), 2);
+    assert_eq!(server.request_count("/media/MnOpQr789/source.mov"), 1);
+    assert_eq!(
+        std::fs::read(member_dir.join(SECOND)).unwrap(),
+        media_body("/media/GhIjKl456/source.gif")
+    );
+    assert!(verify_archive(dir.path()).await.unwrap().is_ok());
+}
+
+#[tokio::test]
+async fn incomplete_files_are_kept_when_download_fails() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    let member_dir = dir.path().join("mockchannel");
+    archive(server.url(), dir.path()).await;
+    std::fs::write(member_dir.join(SECOND), b"fake").unwrap();
+    let media = "/media/GhIjKl456/source.gif";
+    server.reset_route(media);
+    server.route(media, MockResponse::status(404));
+
+    archive(server.url(), dir.path()).await;
+
+    assert_eq!(std::fs::read(member_dir.join(SECOND)).unwrap(), b"fake");
+}
+
+#[tokio::test]
+async fn download_not_matching_listed_size_is_kept() {
+    let server = giphy_mock("").await;
+    let media = "/media/aBcDeF123/source.mp4";
+    server.reset_route(media);
+    server.route(media, MockResponse::ok("short"));
+    let dir = tempfile::tempdir().unwrap();
+    let client = GiphyClient::builder()
+        .base_url(server.url())
+        .retry(RetryPolicy::none())
+        .build()
+        .unwrap();
+
+    let gif = client.gif(&"aBcDeF123".parse().unwrap()).await.unwrap();
+    client.download_gif(gif, dir.path()).await.unwrap();
+
+    let member_dir = dir.path().join("mockchannel");
+    assert_eq!(std::fs::read(member_dir.join(FIRST)).unwrap(), b"short");
+    assert!(!member_dir.join(format!("{}.part", FIRST)).exists());
+}
+
+#[tokio::test]
+async fn cli_verify_fails_on_damaged_archive() {
+    let server = giphy_mock("").await;
+    let dir = tempfile::tempdir().unwrap();
+    archive(server.url(), dir.path()).await;
+    std::fs::remove_file(dir.path().join("mockchannel").join(FIRST)).unwrap();
+
+    let output = tokio::task::spawn_blocking({
+        let dir = dir.path().to_owned();
+        move || {
+            Command::new(env!("CARGO_BIN_EXE_giphy-download"))
+                .args(["verify", "--directory"])
+                .arg(dir)
+                .output()
+                .unwrap()
+        }
+    })
+    .await
+    .unwrap();
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(&format!("{}: missing", FIRST)));
+}